@@ -6,6 +6,8 @@ use engage::{
     gamedata::{item::ItemData, unit::Unit, Gamedata, JobData, WeaponMask}, noticemanager::NoticeManager, proc::ProcInst, script::{DynValue, EventResultScriptCommand, EventScript, EventScriptCommand, ScriptUtils}
 };
 
+use crate::api::vibrations::cobapi_run_vibration_event;
+
 #[unity::from_offset("App", "ScriptUtil", "GetSequence")]
 pub extern "C" fn scriptutil_getsequence(_method_info: OptionalMethod) -> &'static ProcInst;
 
@@ -49,6 +51,8 @@ impl ScriptCobalt {
         // local x = HasPurchasedSeasonPass() -- bool
         event.register_function("HasPurchasedSeasonPass", cobaltsystem_has_seasonpass);
         event.register_action("AddBondRing", add_bond_ring);
+        // Vibrate(name)
+        event.register_action("Vibrate", cobaltsystem_vibrate);
 
         // Process the callbacks to register lua methods
         EVENTSCRIPT_CB.lock().unwrap().iter().for_each(|cb| cb(event));
@@ -79,6 +83,14 @@ extern "C" fn add_bond_ring(args: &Il2CppArray<DynValue>, _method_info: Optional
     }
 }
 
+extern "C" fn cobaltsystem_vibrate(args: &Il2CppArray<DynValue>, _method_info: OptionalMethod) {
+    let name = args.try_get_string(0).unwrap_or_else(|| {
+        panic!("Vibrate: No vibration event name was provided");
+    });
+
+    cobapi_run_vibration_event(name);
+}
+
 extern "C" fn cobaltsystem_notice(args: &Il2CppArray<DynValue>, _method_info: OptionalMethod) {
     let string = args.try_get_string(0).unwrap();
     NoticeManager::add(string);
@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use unity::prelude::*;
+
+use gamedata::gamedata::{EasingType, VibrationEvent, VibrationType};
+
+use crate::{
+    vibrationevents::register_vibration_event,
+    vibrations::{queue_handlers::run_vibration_event_by_name, util::do_vibrate},
+};
+
+/// Run a vibration event or chain by name, as if the game had triggered it.
+///
+/// Nothing happens if the player turned vibrations off or the game is rewinding.
+#[no_mangle]
+pub extern "C" fn cobapi_run_vibration_event(name: &Il2CppString) {
+    let name = name.to_string();
+
+    do_vibrate(|| {
+        run_vibration_event_by_name(&name);
+    });
+}
+
+#[repr(C)]
+/// Values of a vibration event registered through the API, with the same meaning as in VibrationEvent.xml.
+pub struct VibrationEventParams {
+    /// Duration in seconds.
+    pub time: f32,
+    pub amplitude_magnitude: f32,
+    pub amp_low: f32,
+    pub amp_high: f32,
+    pub freq_low: f32,
+    pub freq_high: f32,
+}
+
+/// Register a vibration event that can then be run by name, like the ones defined in VibrationEvent.xml.
+///
+/// `easing` and `vibration_type` are optional and take the same values as the XML.
+#[no_mangle]
+pub extern "C" fn cobapi_register_vibration_event(
+    name: &Il2CppString,
+    params: &VibrationEventParams,
+    easing: Option<&Il2CppString>,
+    vibration_type: Option<&Il2CppString>,
+) {
+    let name = name.to_string();
+
+    println!("CobAPI received a vibration event registration for '{}'", name);
+
+    let easing_type = parse_optional::<EasingType>(easing, "easing", &name);
+    let vibration_type = parse_optional::<VibrationType>(vibration_type, "vibration type", &name);
+
+    register_vibration_event(VibrationEvent {
+        name,
+        time: params.time,
+        amplitude_magnitude: params.amplitude_magnitude,
+        amp_low: params.amp_low,
+        amp_high: params.amp_high,
+        freq_low: params.freq_low,
+        freq_high: params.freq_high,
+        easing_type,
+        vibration_type,
+        keyframes: vec![],
+    });
+}
+
+fn parse_optional<T: FromStr>(value: Option<&Il2CppString>, kind: &str, event_name: &str) -> Option<T> {
+    let value = value?.to_string();
+
+    if value.is_empty() {
+        return None;
+    }
+
+    match T::from_str(&value) {
+        Ok(x) => Some(x),
+        Err(_) => {
+            println!("CobAPI: Unknown {} '{}' for vibration event '{}', ignoring it", kind, value, event_name);
+            None
+        },
+    }
+}
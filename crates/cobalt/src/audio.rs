@@ -21,8 +21,11 @@ use camino::Utf8PathBuf;
 pub mod gamesound;
 pub mod soundmanager;
 pub mod soundplay;
+pub mod voicechain;
 pub mod wwise;
 
+use voicechain::VoiceChain;
+
 pub const COBALT_EVENT_MARKER_PREFIX: &str = "CobaltEvent:";
 pub const ORIGINAL_SUFFIX: &str = "_Original";
 
@@ -30,77 +33,19 @@ pub static PLAY_ORIGINAL_V_PICK: AtomicBool = AtomicBool::new(false);
 pub static mut PREVIOUS_LAST_PICK_VOICE: u8 = 0;
 pub static mut UNSAFE_CHARACTER_PTR: *const Character = std::ptr::null();
 
-fn get_event_or_fallback(event_name: &Il2CppString) -> &Il2CppString {
-    match ParsedVoice::parse(event_name) {
-        ParsedVoice::ModdedVoiceEvent(mod_voice) => {
-            let mod_string = mod_voice.mod_event.to_string();
-            let mod_str = mod_string.as_str();
-            let fallback_string = mod_voice.fallback_event.to_string();
-            let fallback_str = fallback_string.as_str();
-
-            match mod_str.rsplit_once('_') {
-                Some((event_body_str, event_main_str)) => {
-                    let event_main = Il2CppString::new(format!("{}_{}", event_body_str, event_main_str));
-
-                    if GameSound::is_event_loaded(event_main) {
-                        event_main
-                    } else {
-                        let event_fallback = Il2CppString::new(format!("{}_{}", event_body_str, fallback_str));
-                        event_fallback
-                    }
-                },
-                None => event_name,
-            }
-        },
-        ParsedVoice::DefaultVoiceEvent(default_voice) => {
-            println!("Getting Default voice: {}", default_voice.event);
-            default_voice.event
-        },
-    }
-}
-
-enum ParsedVoice<'a> {
-    ModdedVoiceEvent(ModdedVoiceEvent<'a>),
-    DefaultVoiceEvent(DefaultVoiceEvent<'a>),
-}
-
-/// Represents a modded voice name with a fallback option.
-///
-/// For example, in `Voice="SeasideDragon!PlayerF"`, `SeasideDragon` is the modded voice name,
-/// and `PlayerF` is the fallback voice name that will be used if the modded voice is not available.
-struct ModdedVoiceEvent<'a> {
-    /// The name of the modded voice to be used, e.g., `SeasideDragon`.
-    mod_event: &'a Il2CppString,
-    /// The fallback voice name, e.g., `PlayerF`, used if the modded voice is unavailable.
-    /// This is not necessarily a voice name from the vanilla game - it could be another modded voice name (though this has not been tested).
-    fallback_event: &'a Il2CppString,
-}
-
-/// Represents an original voice name without any fallbacks.
-struct DefaultVoiceEvent<'a> {
-    event: &'a Il2CppString,
-}
-
-impl ParsedVoice<'_> {
-    fn parse(event_name: &Il2CppString) -> ParsedVoice {
-        let event_string = event_name.to_string();
-        let parts: Vec<&str> = event_string.split('!').collect();
-
-        if parts.len() == 2 {
-            return ParsedVoice::ModdedVoiceEvent(ModdedVoiceEvent {
-                mod_event: Il2CppString::new(parts[0]),
-                fallback_event: Il2CppString::new(parts[1]),
-            });
-        } else {
-            return ParsedVoice::DefaultVoiceEvent(DefaultVoiceEvent { event: event_name });
-        }
-    }
+/// Check if Wwise knows about an event, for use with [`VoiceChain::resolve_event`].
+fn is_event_loaded(event_name: &str) -> bool {
+    GameSound::is_event_loaded(Il2CppString::new(event_name))
 }
 
+/// Get the name a Person switch should actually be set to, for voice names with fallbacks like `Costume!BaseMod!PlayerF`.
 fn get_switchname_fallback(switch_name: &Il2CppString) -> &Il2CppString {
-    match switch_name.to_string().split('!').nth(1) {
-        Some(switch_fallback) => Il2CppString::new(switch_fallback),
-        None => switch_name,
+    let switch_string = switch_name.to_string();
+    let chain = VoiceChain::parse(&switch_string);
+
+    match chain.switch_name() {
+        Some(switch_fallback) if chain.has_fallbacks() => Il2CppString::new(switch_fallback),
+        _ => switch_name,
     }
 }
 
@@ -119,13 +64,9 @@ pub fn unitinfo_reservecharavoice(
     // println!("[UnitInfo] Person Switch name: {}", person_string);
 
     match event_string.as_str() {
-        "V_Engage_Respond" => {
-            let modded_event = Il2CppString::new(format!("{}_{}", event_string, person_string));
-
-            match GameSound::is_event_loaded(modded_event) {
-                true => call_original!(side, person_switch_name, engage_switch_name, modded_event, method_info),
-                false => call_original!(side, person_switch_name, engage_switch_name, event_name, method_info),
-            }
+        "V_Engage_Respond" => match VoiceChain::parse(&person_string).resolve_event(&event_string, is_event_loaded) {
+            Some(modded_event) => call_original!(side, person_switch_name, engage_switch_name, Il2CppString::new(modded_event), method_info),
+            None => call_original!(side, person_switch_name, engage_switch_name, event_name, method_info),
         },
 
         _ => call_original!(side, person_switch_name, engage_switch_name, event_name, method_info),
@@ -1,7 +1,15 @@
+use std::sync::Once;
+
+use camino::Utf8PathBuf;
 use unity::prelude::*;
 use engage::{combat::Character, gamedata::unit::Unit, gamesound::GameSound};
 
-use crate::audio::{get_event_or_fallback, get_switchname_fallback, ORIGINAL_SUFFIX, PREVIOUS_LAST_PICK_VOICE, UNSAFE_CHARACTER_PTR};
+use crate::audio::{get_switchname_fallback, is_event_loaded, voicechain::VoiceChain, ORIGINAL_SUFFIX, PREVIOUS_LAST_PICK_VOICE, UNSAFE_CHARACTER_PTR};
+
+const SOUNDBANK_DIR: &str = "Data/StreamingAssets/Audio/GeneratedSoundBanks/Switch";
+
+/// Banks are only checked for conflicts once per boot, no matter how many times the default banks are loaded.
+static SOUNDBANKS_CHECKED: Once = Once::new();
 
 pub struct GameSoundStatic {
     default_bank_name_array: &'static mut Il2CppArray<&'static mut Il2CppString>,
@@ -12,21 +20,33 @@ pub fn load_default_sound_banks(method_info: OptionalMethod) {
     let static_fields = GameSound::class().get_static_fields_mut::<GameSoundStatic>();
     let mut banks = static_fields.default_bank_name_array.to_vec();
 
+    let vanilla_banks: Vec<String> = banks.iter().map(|bank| bank.to_string()).collect();
+    let mut mod_banks = vec![];
+
     let manager = mods::manager::Manager::get();
 
-    if let Ok(dir) = manager.get_directory("Data/StreamingAssets/Audio/GeneratedSoundBanks/Switch") {
+    // Nothing has been opened by Wwise yet, so this is the time to clean up what zipped mods no longer need
+    crate::audio::wwise::prune_extracted_files();
+
+    if let Ok(dir) = manager.get_directory(SOUNDBANK_DIR) {
         if let Ok(filepaths) = manager.get_files_in_directory_and_subdir(dir) {
-            for path in filepaths.iter().filter(|path| path.extension() == Some("bnk")) {
+            for path in filepaths.into_iter().filter(|path| path.extension() == Some("bnk")) {
                 let filename = Il2CppString::new_static(path.file_stem().unwrap());
 
                 if !banks.contains(&filename) {
                     println!("Added '{}' to the list", path);
                     banks.insert(0, filename);
                 }
+
+                mod_banks.push(path);
             }
         }
     }
 
+    if !mod_banks.is_empty() {
+        SOUNDBANKS_CHECKED.call_once(|| check_soundbanks(&vanilla_banks, &mod_banks));
+    }
+
     static_fields.default_bank_name_array = Il2CppArray::from_slice(banks.as_mut_slice()).unwrap();
 
     // static_fields.default_bank_name_array.iter().for_each(|bank| {
@@ -36,6 +56,55 @@ pub fn load_default_sound_banks(method_info: OptionalMethod) {
     call_original!(method_info);
 }
 
+/// Report the modded banks that conflict with the game's or with each other.
+///
+/// Only the BKHD and HIRC sections are read, since the embedded media is most of the file and isn't needed to find the events.
+fn check_soundbanks(vanilla_banks: &[String], mod_banks: &[Utf8PathBuf]) {
+    let manager = mods::manager::Manager::get();
+
+    let mod_banks: Vec<(String, wwise::SoundBank)> = mod_banks
+        .iter()
+        .filter_map(|path| match manager.read_file(path, wwise::SoundBank::read_hierarchy) {
+            Ok(Ok(bank)) => {
+                println!("[GameSound] '{}' has {} events", path, bank.events.len());
+                Some((path.to_string(), bank))
+            },
+            Ok(Err(err)) => {
+                println!("[GameSound] Could not parse soundbank '{}': {}", path, err);
+                None
+            },
+            Err(err) => {
+                println!("[GameSound] Could not read soundbank '{}': {}", path, err);
+                None
+            },
+        })
+        .collect();
+
+    // Vanilla banks a mod replaces are left unread, their events don't count anymore
+    let vanilla_banks: Vec<(String, Option<wwise::SoundBank>)> = vanilla_banks
+        .iter()
+        .map(|name| {
+            let path = format!("{}/{}.bnk", SOUNDBANK_DIR, name);
+
+            let bank = if manager.exists(&path) {
+                None
+            } else {
+                std::fs::File::open(format!("rom:/{}", path))
+                    .map_err(|err| err.to_string())
+                    .and_then(|file| wwise::SoundBank::read_hierarchy(std::io::BufReader::new(file)).map_err(|err| err.to_string()))
+                    .inspect_err(|err| println!("[GameSound] Could not read vanilla soundbank '{}': {}", name, err))
+                    .ok()
+            };
+
+            (name.to_owned(), bank)
+        })
+        .collect();
+
+    for collision in wwise::find_collisions(&vanilla_banks, &mod_banks) {
+        println!("[GameSound] Soundbank conflict: {}", collision);
+    }
+}
+
 #[skyline::hook(offset = 0x2292270)]
 pub fn gamesound_personvoice(
     gameobject: &(),
@@ -89,36 +158,41 @@ pub fn gamesound_personvoice(
         return;
     }
 
-    let parsed_event = get_event_or_fallback(Il2CppString::new(format!("{}_{}", event_string, person_switch_name)));
+    // Look for an event provided for one of the voices, starting from the most preferred one
+    let person_string = person_switch_name.to_string();
+    let parsed_event = VoiceChain::parse(&person_string).resolve_event(&event_string, is_event_loaded);
     let parsed_switchname = get_switchname_fallback(person_switch_name);
 
-    if GameSound::is_event_loaded(parsed_event) {
-        println!("[PersonVoice True]: Event name: {}, switch name: {}", parsed_event, parsed_switchname);
-        call_original!(gameobject, parsed_switchname, engage_switch_name, parsed_event, character, method_info);
-    } else {
-        println!("[PersonVoice False]: Event name: {}, switch name: {}", event_string, parsed_switchname);
-        call_original!(gameobject, parsed_switchname, engage_switch_name, event_name, character, method_info);
+    match parsed_event {
+        Some(parsed_event) => {
+            println!("[PersonVoice True]: Event name: {}, switch name: {}", parsed_event, parsed_switchname);
+            call_original!(gameobject, parsed_switchname, engage_switch_name, Il2CppString::new(parsed_event), character, method_info);
+        },
+        None => {
+            println!("[PersonVoice False]: Event name: {}, switch name: {}", event_string, parsed_switchname);
+            call_original!(gameobject, parsed_switchname, engage_switch_name, event_name, character, method_info);
+        },
     }
 }
 
 #[skyline::hook(offset = 0x2292F90)]
 pub fn gamesound_ringcleaningvoice(person_switch_name: &Il2CppString, event_name: &Il2CppString, character: &Character, method_info: OptionalMethod) {
     let event_string = event_name.to_string();
-    let person_string = person_switch_name.to_string();
+    let parsed_switchname = get_switchname_fallback(person_switch_name);
 
-    match event_string.contains("V_Ring") {
-        true => {
-            let modded_event = Il2CppString::new(format!("{}_{}", event_string, person_string));
+    if !event_string.contains("V_Ring") {
+        return call_original!(parsed_switchname, event_name, character, method_info);
+    }
 
-            println!("[GameSound] {} => {}_{}", event_string, event_string, person_string);
+    // Look for an event provided for one of the voices, starting from the most preferred one
+    let person_string = person_switch_name.to_string();
 
-            match GameSound::is_event_loaded(modded_event) {
-                true => call_original!(person_switch_name, modded_event, character, method_info),
-                false => call_original!(person_switch_name, event_name, character, method_info),
-            }
+    match VoiceChain::parse(&person_string).resolve_event(&event_string, is_event_loaded) {
+        Some(parsed_event) => {
+            println!("[GameSound] {} => {}, switch name: {}", event_string, parsed_event, parsed_switchname);
+            call_original!(parsed_switchname, Il2CppString::new(parsed_event), character, method_info)
         },
-
-        false => call_original!(person_switch_name, event_name, character, method_info),
+        None => call_original!(parsed_switchname, event_name, character, method_info),
     }
 }
 
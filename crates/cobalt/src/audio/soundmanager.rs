@@ -1,7 +1,7 @@
 use engage::{combat::Character, gamesound::GameSound, mapmind::MapMind, soundmanager::SoundManager};
 use unity::prelude::*;
 
-use crate::audio::{combat_character_appearance_create_for_sound, ParsedVoice, PLAY_ORIGINAL_V_PICK};
+use crate::audio::{combat_character_appearance_create_for_sound, is_event_loaded, voicechain::VoiceChain, PLAY_ORIGINAL_V_PICK};
 
 // App.SoundManager$$IsEventPlaying	71024f5520	bool App.SoundManager$$IsEventPlaying(App_SoundManager_o * __this, System_String_o * eventName, MethodInfo * method)	28
 #[unity::hook("App", "SoundManager", "IsEventPlaying")]
@@ -31,13 +31,10 @@ pub fn soundmanager_iseventplaying(this: &SoundManager, event_name: Option<&Il2C
 // First, we get the unit that is currently being selected.
 // Then, we get the voice field of the unit.
 //
-// If it's a custom voice line (for example, "SeasideDragon!PlayerF"), we check if the custom V_Pick event is loaded (for example, "V_Pick_SeasideDragon").
-// If it is, we play it.
+// If it's a custom voice line (for example, "Costume!SeasideDragon!PlayerF"), we go through the voices from left to right
+// and play the first V_Pick event that is loaded (for example, "V_Pick_Costume", then "V_Pick_SeasideDragon", then "V_Pick_PlayerF").
 //
-// If it's not, we check if the fallback event is loaded (for example, "V_Pick_PlayerF").
-// If it is, we play it.
-//
-// If neither the custom event nor the fallback event is loaded, we try to play the V_Pick_<ascii_name> event.
+// If none of the voices have a V_Pick event loaded, we try to play the V_Pick_<ascii_name> event.
 // If it is loaded, we play it.
 //
 // If none of the above events are loaded, we play the default V_Pick event.
@@ -65,29 +62,18 @@ pub fn soundmanager_postevent_with_temporarygameobject(
             // Read the `Voice` value. This corresponds to the `Voice` set via the AssetTable.
             let voice_name = unsafe { combat_character_appearance_create_for_sound(my_unit, None).sound.voice_id };
 
-            // Check if the voice_name is a special one that defines a main voice and a fallback like `Voice="SeasideDragon!PlayerF"``
-            let voice_name_parsed = ParsedVoice::parse(voice_name);
+            // Check if the voice_name is a special one that defines a main voice and fallbacks like `Voice="SeasideDragon!PlayerF"`
+            let voice_string = voice_name.to_string();
+            let voice_chain = VoiceChain::parse(&voice_string);
 
-            match ParsedVoice::parse(voice_name) {
-                ParsedVoice::DefaultVoiceEvent(_) => {
-                   // do nothing - legacy behavior
-                },
-                ParsedVoice::ModdedVoiceEvent(modded_voice) => {
-                    let modded_event = Il2CppString::new(format!("{}_{}", event_string, modded_voice.mod_event));
-                    // Check if we have a loaded custom V_Pick, that looks like V_Pick_<unit_name>, for example V_Pick_SeasideDragon
-                    // if we do, play it.
-                    if GameSound::is_event_loaded(modded_event) {
-                        println!("[SoundManager] Found modded event: {}", modded_event);
-                        return call_original!(this, modded_event, gameobject, character, is_get_position, method_info);
-                    }
-                    // Attempt to use the fallback event if the custom V_Pick is not loaded
-                    // For example, this will support a V_Pick_PlayerF event that perhaps other mod might add.
-                    let fallback_event = Il2CppString::new(format!("{}_{}", event_string, modded_voice.fallback_event));
-                    if GameSound::is_event_loaded(fallback_event) {
-                        println!("[SoundManager] Found fallback event: {}", fallback_event);
-                        return call_original!(this, fallback_event, gameobject, character, is_get_position, method_info);
-                    }
-                },
+            // Voices without fallbacks keep the legacy behavior
+            if voice_chain.has_fallbacks() {
+                // Check if we have a loaded custom V_Pick for any of the voices, that looks like V_Pick_<voice>, for example V_Pick_SeasideDragon
+                // This also supports a V_Pick_PlayerF event that perhaps another mod might add.
+                if let Some(modded_event) = voice_chain.resolve_event(&event_string, is_event_loaded) {
+                    println!("[SoundManager] Found modded event: {}", modded_event);
+                    return call_original!(this, Il2CppString::new(modded_event), gameobject, character, is_get_position, method_info);
+                }
             }
 
             // Try to find an ASCII name fallback for the character. If it exists, use it.
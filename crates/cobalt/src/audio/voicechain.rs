@@ -0,0 +1,104 @@
+/// Separates the voices of a chain, as in `Costume!BaseMod!PlayerF`.
+pub const VOICE_SEPARATOR: char = '!';
+
+/// A voice name with fallbacks, as set in the `Voice` field of the AssetTable or in a Person switch.
+///
+/// For example, in `Voice="Costume!BaseMod!PlayerF"`, `Costume` is the voice we would like to hear.
+/// If an event is not provided for it, we fall back to `BaseMod` (which can be another mod's voice), and then to `PlayerF`.
+/// The last voice is expected to be one the game knows about, since it's the one Wwise's Person switch ends up set to.
+///
+/// A voice name without any `!` is a chain of a single voice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceChain<'a> {
+    voices: Vec<&'a str>,
+}
+
+impl<'a> VoiceChain<'a> {
+    pub fn parse(voice_name: &'a str) -> Self {
+        let voices = voice_name.split(VOICE_SEPARATOR).filter(|voice| !voice.is_empty()).collect();
+
+        Self { voices }
+    }
+
+    /// Every voice of the chain, from the most to the least preferred.
+    pub fn voices(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.voices.iter().copied()
+    }
+
+    /// Has fallbacks, which only happens for voices added by mods.
+    pub fn has_fallbacks(&self) -> bool {
+        self.voices.len() > 1
+    }
+
+    /// The voice to set the Person switch to, which is the last one of the chain.
+    pub fn switch_name(&self) -> Option<&'a str> {
+        self.voices.last().copied()
+    }
+
+    /// Go through the chain left to right and return the first `<event>_<voice>` that is loaded.
+    pub fn resolve_event(&self, event: &str, is_event_loaded: impl Fn(&str) -> bool) -> Option<String> {
+        self.voices()
+            .map(|voice| format!("{}_{}", event, voice))
+            .find(|voice_event| is_event_loaded(voice_event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VoiceChain;
+
+    fn loaded(events: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |event| events.contains(&event)
+    }
+
+    #[test]
+    fn plain_voices_are_single_chains() {
+        let chain = VoiceChain::parse("PlayerF");
+
+        assert!(!chain.has_fallbacks());
+        assert_eq!(chain.switch_name(), Some("PlayerF"));
+        assert_eq!(chain.resolve_event("V_Critical", loaded(&["V_Critical_PlayerF"])), Some(String::from("V_Critical_PlayerF")));
+        assert_eq!(chain.resolve_event("V_Critical", loaded(&[])), None);
+    }
+
+    #[test]
+    fn events_are_resolved_left_to_right() {
+        let chain = VoiceChain::parse("Costume!BaseMod!PlayerF");
+
+        assert!(chain.has_fallbacks());
+        assert_eq!(chain.voices().collect::<Vec<_>>(), vec!["Costume", "BaseMod", "PlayerF"]);
+
+        let everything = loaded(&["V_Critical_Costume", "V_Critical_BaseMod", "V_Critical_PlayerF"]);
+        let no_costume = loaded(&["V_Critical_BaseMod", "V_Critical_PlayerF"]);
+        let vanilla_only = loaded(&["V_Critical_PlayerF"]);
+
+        assert_eq!(chain.resolve_event("V_Critical", everything), Some(String::from("V_Critical_Costume")));
+        assert_eq!(chain.resolve_event("V_Critical", no_costume), Some(String::from("V_Critical_BaseMod")));
+        assert_eq!(chain.resolve_event("V_Critical", vanilla_only), Some(String::from("V_Critical_PlayerF")));
+        assert_eq!(chain.resolve_event("V_Critical", loaded(&[])), None);
+    }
+
+    #[test]
+    fn switch_falls_back_to_the_last_voice() {
+        assert_eq!(VoiceChain::parse("SeasideDragon!PlayerF").switch_name(), Some("PlayerF"));
+        assert_eq!(VoiceChain::parse("Costume!BaseMod!PlayerM").switch_name(), Some("PlayerM"));
+    }
+
+    #[test]
+    fn empty_voices_are_ignored() {
+        let chain = VoiceChain::parse("Costume!!PlayerF!");
+
+        assert_eq!(chain.voices().collect::<Vec<_>>(), vec!["Costume", "PlayerF"]);
+        assert_eq!(VoiceChain::parse("").switch_name(), None);
+    }
+
+    #[test]
+    fn voices_with_underscores_are_kept_whole() {
+        let chain = VoiceChain::parse("Seaside_Dragon!PlayerF");
+
+        assert_eq!(
+            chain.resolve_event("V_Engage_Respond", loaded(&["V_Engage_Respond_Seaside_Dragon"])),
+            Some(String::from("V_Engage_Respond_Seaside_Dragon"))
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use unity::prelude::*;
 
 use std::{
-    str::FromStr,
+    collections::HashSet,
     ffi::{CStr, CString},
+    sync::{LazyLock, RwLock},
 };
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 
 #[repr(i32)]
 #[derive(PartialEq)]
@@ -46,20 +47,163 @@ pub struct WwiseFileHolder {
     handle: skyline::nn::fs::FileHandle,
 }
 
+/// Where files from zipped mods are extracted to, since Wwise needs a real path to open them.
+const EXTRACTED_AUDIO_DIR: &str = "sd:/engage/cache/audio";
+
+/// Hashes of the zipped files that are up to date in the cache during this session, so they are only checked once per boot.
+///
+/// The write lock is held for the whole extraction, so two Wwise opens of the same file can't write it at the same time or read it half-written.
+static EXTRACTED_FILES: LazyLock<RwLock<HashSet<u32>>> = LazyLock::new(|| RwLock::new(HashSet::new()));
+
+/// Find the path on the SD card of a modded file, if a mod provides it.
+fn get_modded_path(relative_path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let manager = mods::manager::Manager::get();
+
+    if !manager.exists(relative_path) {
+        return None;
+    }
+
+    match manager.get_real_path(relative_path) {
+        Ok(Some(path)) => Some(path),
+        Ok(None) => extract_zipped_file(relative_path),
+        Err(err) => {
+            println!("[Wwise] Could not locate '{}': {}", relative_path, err);
+            None
+        },
+    }
+}
+
+/// Extract a file provided by a zipped mod to the cache directory and return its new path.
+///
+/// Files extracted on a previous boot are reused as long as the zipped file has the same size and timestamp.
+fn extract_zipped_file(relative_path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let hash = mods::hash(relative_path);
+    let extracted_path = Utf8Path::new(EXTRACTED_AUDIO_DIR).join(relative_path);
+
+    if EXTRACTED_FILES.read().unwrap().contains(&hash) {
+        return Some(extracted_path);
+    }
+
+    let mut extracted_files = EXTRACTED_FILES.write().unwrap();
+
+    // Someone else might have extracted it while we were waiting for the lock
+    if extracted_files.contains(&hash) {
+        return Some(extracted_path);
+    }
+
+    let manager = mods::manager::Manager::get();
+
+    let (size, stamp) = match (manager.get_file_size(relative_path), manager.get_last_modified(relative_path)) {
+        (Ok(size), Ok(last_modified)) => (size, format!("{} {}", size, last_modified)),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("[Wwise] Could not read the metadata of '{}' in its zipped mod: {}", relative_path, err);
+            return None;
+        },
+    };
+
+    let stamp_path = Utf8PathBuf::from(format!("{}.stamp", extracted_path));
+
+    let up_to_date = std::fs::read_to_string(&stamp_path).is_ok_and(|previous| previous == stamp)
+        && std::fs::metadata(&extracted_path).is_ok_and(|metadata| metadata.len() == size);
+
+    if up_to_date {
+        extracted_files.insert(hash);
+        return Some(extracted_path);
+    }
+
+    let result = manager
+        .get_file(relative_path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| write_atomically(&extracted_path, &file).map_err(|err| err.to_string()))
+        .and_then(|_| std::fs::write(&stamp_path, stamp).map_err(|err| err.to_string()));
+
+    match result {
+        Ok(()) => {
+            extracted_files.insert(hash);
+            Some(extracted_path)
+        },
+        Err(err) => {
+            println!("[Wwise] Could not extract '{}' from its zipped mod: {}", relative_path, err);
+            None
+        },
+    }
+}
+
+/// Write to a temporary file first and then move it over the previous one, so Wwise never opens a half-written file.
+fn write_atomically(path: &Utf8Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp = format!("{}.tmp", path);
+
+    std::fs::write(&temp, content)?;
+
+    // The Switch's filesystem refuses to rename over an existing file
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+        _ => (),
+    }
+
+    std::fs::rename(temp, path)
+}
+
+/// Remove the extracted files that no zipped mod provides anymore, so the cache doesn't keep growing.
+pub fn prune_extracted_files() {
+    let manager = mods::manager::Manager::get();
+
+    let mut directories = vec![Utf8PathBuf::from(EXTRACTED_AUDIO_DIR)];
+
+    while let Some(directory) = directories.pop() {
+        let Ok(entries) = std::fs::read_dir(&directory) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+                continue;
+            };
+
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                directories.push(path);
+                continue;
+            }
+
+            // Stamps and leftovers of an interrupted extraction belong to the file they are named after
+            let extracted_path = match path.extension() {
+                Some("stamp") | Some("tmp") => path.with_extension(""),
+                _ => path.clone(),
+            };
+
+            let Ok(relative_path) = extracted_path.strip_prefix(EXTRACTED_AUDIO_DIR) else {
+                continue;
+            };
+
+            let still_zipped = manager.exists(relative_path) && matches!(manager.get_real_path(relative_path), Ok(None));
+
+            if !still_zipped || path.extension() == Some("tmp") {
+                println!("[Wwise] Removing '{}' from the audio cache", path);
+
+                if let Err(err) = std::fs::remove_file(&path) {
+                    println!("[Wwise] Could not remove '{}': {}", path, err);
+                }
+            }
+        }
+    }
+}
+
 #[skyline::hook(offset = 0x169d700)]
 pub fn wwise_file_open_hook(path: *const i8, unk1: u64, _unk2: *const u8, unk3: &mut WwiseFileHolder) -> u32 {
     let str_path = unsafe { CStr::from_ptr(path).to_str().unwrap() };
-    let utfpath = Utf8PathBuf::from_str(str_path).unwrap();
-
-    unsafe {
-        let relative_path = utfpath.strip_prefix("rom:/").unwrap();
-
-        let manager = mods::manager::Manager::get();
 
-        let res = if let Some(loc) = manager.get_locations().find(|loc| loc == relative_path) {
-            let full_path = manager.get_absolute_full_path(loc).unwrap();
-            let new_path = CString::new(full_path.as_str()).unwrap();
+    let modded_path = Utf8Path::new(str_path)
+        .strip_prefix("rom:/")
+        .ok()
+        .and_then(get_modded_path)
+        .map(|full_path| CString::new(full_path.as_str()).unwrap());
 
+    unsafe {
+        let res = if let Some(new_path) = modded_path {
             skyline::nn::fs::OpenFile(&mut unk3.handle, new_path.as_c_str().to_bytes_with_nul().as_ptr(), 1)
         } else {
             skyline::nn::fs::OpenFile(&mut unk3.handle, path as *const u8, 1)
@@ -5,14 +5,36 @@ use engage::bundle::TextAssetBundle;
 
 use camino::Utf8PathBuf;
 
+use crate::sequences::mainmenu::cobaltmenu::sequences::settings::bundle_loading::LOAD_FROM_MEMORY;
+
 #[unity::from_offset("UnityEngine", "AssetBundle", "LoadFromMemoryAsync_Internal(System.Byte[],System.UInt32)")]
 pub fn assetbundle_loadfrommemoryasync_internal(file: &'static mut Il2CppArray<u8>, idk: u32, method_info: OptionalMethod) -> *const u8;
 
+#[unity::from_offset("UnityEngine", "AssetBundle", "LoadFromFileAsync_Internal(System.String,System.UInt32,System.UInt64)")]
+pub fn assetbundle_loadfromfileasync_internal(path: &Il2CppString, crc: u32, offset: u64, method_info: OptionalMethod) -> *const u8;
+
 #[skyline::hook(offset = 0x3f41ff0)]
 pub fn irawbundle_load_hook(path: &Il2CppString, method_info: OptionalMethod) -> *const u8 {
-    let orig_path = &mut Utf8PathBuf::from(&path.to_string());
+    let orig_path = Utf8PathBuf::from(&path.to_string());
+
+    let Ok(rel_path) = orig_path.strip_prefix("rom:/") else {
+        return call_original!(path, method_info);
+    };
+
+    let manager = mods::manager::Manager::get();
+
+    if !manager.exists(rel_path) {
+        return call_original!(path, method_info);
+    }
+
+    // Bundles in a directory can be streamed by Unity from the SD, only zipped ones have to be read into memory first
+    if !*LOAD_FROM_MEMORY.read().unwrap() {
+        if let Ok(Some(real_path)) = manager.get_real_path(rel_path) {
+            return unsafe { assetbundle_loadfromfileasync_internal(Il2CppString::new(real_path.as_str()), 0, 0, None) };
+        }
+    }
 
-    match mods::manager::Manager::get().get_file(orig_path.strip_prefix("rom:/").unwrap()) {
+    match manager.get_file(rel_path) {
         Ok(content) => {
             let array = Il2CppArray::from_slice(content).unwrap();
 
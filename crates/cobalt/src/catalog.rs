@@ -7,20 +7,81 @@ use addressables_rs::{
     lookup::ExtraId,
 };
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use astra_formats::Asset;
 
 use crate::api::events::{publish_system_event, SystemEvent};
 
+mod assettype;
+mod cache;
+mod classid;
+mod collision;
+mod contentdata;
+mod dependency;
+mod internalpath;
+mod pool;
+pub mod report;
+mod sidecar;
+
+use assettype::{read_resource_types, AssetKind, ProviderTypes, MISSING_ASSET_TYPE};
+use cache::{hash_lut, CatalogCache};
+use classid::read_class_ids;
+use collision::{find_collisions, BundleIdentity, CollisionError};
+use contentdata::ContentCatalogData;
+use dependency::{merge_dependencies, DependencyError, DependencyResolver};
+use internalpath::InternalPathRules;
+use report::{get_mod_name, report_bundle_error, write_report};
+use sidecar::BundleSidecar;
+
+const CATALOG_CACHE_PATH: &str = "sd:/engage/catalog.lut";
+
+/// How many bundles can be inspected at the same time. The game keeps a core for itself, so we don't need more than this.
+const WORKER_COUNT: usize = 3;
+
+/// Total size of the bundles being inspected at once. Parsing needs a few times the size of the file, so this is kept well below what's available.
+const INSPECTION_MEMORY_LIMIT: u64 = 64 * 1024 * 1024;
+
 #[derive(miniserde::Serialize, miniserde::Deserialize, Default)]
 pub struct CachedCatalogEntry {
     internal_id: String,
-    container_internal_id: String,
     primary_key: String,
+    /// Name of the asset file inside of the bundle, which other bundles use to depend on this one.
+    cab_name: String,
+    /// Files the bundle depends on, as written in the bundle. They are resolved again on every boot since the bundles of other mods can come and go.
+    externals: Vec<String>,
+    /// Every addressable asset found in the container of the bundle.
+    entries: Vec<CachedAssetEntry>,
+    /// The bundle replaces one the game already has, so only the dependencies of its assets are updated in the Catalog.
+    replaces_vanilla: bool,
+    last_modified: u64,
+    /// Timestamp of the sidecar of the bundle, if it has one.
+    sidecar_last_modified: Option<u64>,
+    /// Signature of the rules of the mod the internal paths were built with.
+    path_rules_signature: u64,
+}
+
+#[derive(miniserde::Serialize, miniserde::Deserialize, Default)]
+pub struct CachedAssetEntry {
+    container_internal_id: String,
     internal_path: String,
-    dependencies: Vec<String>,
     asset_type: i32,
-    last_modified: u64,
+}
+
+/// A modded bundle to add to the Catalog.
+struct ModdedBundle {
+    rel_path: Utf8PathBuf,
+    entry: CachedCatalogEntry,
+    /// Shared by every asset of the bundle, starting with the bundle itself.
+    dependencies: Vec<String>,
+    /// Rules of the mod providing the bundle, to build the internal path of its assets.
+    path_rules: InternalPathRules,
+}
+
+/// Assets of a replacement bundle that the Catalog already has, whose dependencies are updated once it is serialized.
+struct ReplacedAssets {
+    rel_path: Utf8PathBuf,
+    internal_ids: Vec<String>,
+    dependencies: Vec<String>,
 }
 
 #[skyline::hook(offset = 0x2586040)]
@@ -29,17 +90,16 @@ pub fn from_json_hook(json: &Il2CppString, method_info: OptionalMethod) -> *cons
 
     if let Ok(dir) = manager.get_directory("Data/StreamingAssets/aa/Switch") {
 
+        let lut_content = std::fs::read_to_string("sd:/engage/cache.lut").expect("Could not read `sd:/engage/cache.lut`, make sure to download it on Github");
+
         // Lookup Table to do the glue between modded bundles and the official Catalog
-        let lut_cache: HashMap<String, String> = miniserde::json::from_str(
-            &std::fs::read_to_string("sd:/engage/cache.lut").expect("Could not read `sd:/engage/cache.lut`, make sure to download it on Github"),
-        ).unwrap();
+        let lut_cache: HashMap<String, String> = miniserde::json::from_str(&lut_content).unwrap();
 
         // Catalog cache of the files that exist on the SD
-        let mut cache: HashMap<String, CachedCatalogEntry> = if let Ok(lut) = std::fs::read_to_string("sd:/engage/catalog.lut") {
-            miniserde::json::from_str(&lut).unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
+        let mut cache = CatalogCache::load(CATALOG_CACHE_PATH, hash_lut(&lut_content));
+
+        // Entries are moved back to the cache as their bundle is found, so the ones left behind belong to bundles that no longer exist
+        let mut previous_entries = std::mem::take(&mut cache.entries);
     
         let mut cache_modified = false;
 
@@ -51,196 +111,358 @@ pub fn from_json_hook(json: &Il2CppString, method_info: OptionalMethod) -> *cons
         // Copy the ExtraId here once and for all, since there is no need for us to make our own for every file.
         let extra = catalog.get_extra(ExtraId(200)).expect("Couldn't get ExtraId").to_owned();
 
-        // Timer for the process of processing bundles or adding them from the cache
-        let bundle_timer = std::time::Instant::now();
+        // Provider types the modded assets are registered with, the Catalog is only looked at for the kinds without a known index
+        let mut provider_types = ProviderTypes::new(|| read_resource_types(&json.to_string()));
 
-        manager
-        .get_files_in_directory_and_subdir(dir)
-        .unwrap()
-        .iter()
-        .filter(|relative| relative.extension().unwrap() == "bundle")
-        .for_each(|rel_path| {
-            println!("Processing '{}'", rel_path);
-
-            // Check if we already have this entry cached
-            if let Some(entry) = cache.get(&rel_path.to_string()) {
-                // Make sure this entry doesn't already exist in the Catalog, because if it does, we don't need to append
-                if catalog.get_internal_id_index(&entry.internal_id).is_none() {
-                    // Our cache entry is still relevant, insert the entry in the Catalog and return right away
-                    if entry.last_modified == manager.get_last_modified(rel_path).unwrap() {
-                        // Add the bundle to the Catalog
-                        if let Err(err) = catalog.add_bundle(&entry.internal_id, &entry.primary_key, extra.clone()) {
-                            print_catalog_error(entry.internal_id.as_str(), err)
-                        }
+        // Timer for the process of finding the bundles and checking them against the cache
+        let discovery_timer = std::time::Instant::now();
 
-                        // Add the prefab, and return on success
-                        match catalog.add_prefab(entry.asset_type, entry.container_internal_id.clone(), entry.internal_path.clone(), &entry.dependencies) {
-                            Ok(_) => return,
-                            Err(err) => print_catalog_error(entry.container_internal_id.as_str(), err),
-                        }
-                    } else {
-                        // The timestamp doesn't match, we'll have to scrub the entry
-                        println!("Timestamp does not match, invalidating entry");
-                    }
-                }
-            }
+        // Indices of the bundles that are not cached and have to be inspected
+        let mut uncached: Vec<usize> = Vec::new();
+
+        // Internal path rules of every mod, since its config.yaml can add to the ones of the game
+        let mut mod_path_rules: HashMap<Utf8PathBuf, InternalPathRules> = HashMap::new();
+
+        let mut bundle_paths: Vec<Utf8PathBuf> = manager
+            .get_files_in_directory_and_subdir(dir)
+            .unwrap()
+            .into_iter()
+            .filter(|relative| relative.extension() == Some("bundle"))
+            .collect();
+
+        // Files come in the order the mods were discovered in, which depends on the layout of the SD.
+        // Sort them so collisions are settled the same way on every boot.
+        bundle_paths.sort();
+
+        // Every bundle has to be known before resolving dependencies, since modded bundles can depend on each other.
+        let bundles: Vec<ModdedBundle> = bundle_paths
+            .into_iter()
+            .enumerate()
+            .map(|(idx, rel_path)| {
+                println!("Processing '{}'", rel_path);
+
+                let primary_key = rel_path.strip_prefix("Data/StreamingAssets/aa/Switch").unwrap().to_path_buf();
 
-            println!("File not found in cache");
+                let internal_id = Utf8PathBuf::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch")
+                    .join(primary_key.as_str().to_lowercase());
 
-            let primary_key = rel_path.strip_prefix("Data/StreamingAssets/aa/Switch").unwrap().to_path_buf();
+                // println!("InternalId: {}", internal_id);
+                // println!("Primary Key: {}", primary_key);
 
-            let internal_id = Utf8PathBuf::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch")
-                .join(primary_key.as_str().to_lowercase());
+                // If the file already exists in the game, it might still bring new dependencies with it
+                let replaces_vanilla = catalog.get_internal_id_index(&internal_id).is_some();
 
-            // println!("InternalId: {}", internal_id);
-            // println!("Primary Key: {}", primary_key);
+                let last_modified = manager.get_last_modified(&rel_path).unwrap();
+                let sidecar_last_modified = manager.get_last_modified(BundleSidecar::path(&rel_path)).ok();
 
-            // Check if the file already exists in the game, and ignore it if it does.
-            if catalog.get_internal_id_index(&internal_id).is_none() {
-                if let Err(err) = catalog.add_bundle(&internal_id, &primary_key, extra.clone()) {
-                    print_catalog_error(internal_id.as_str(), err)
+                let path_rules = match manager.get_mod_root(&rel_path) {
+                    Ok(root) => mod_path_rules
+                        .entry(root.to_path_buf())
+                        .or_insert_with(|| get_mod_path_rules(&rel_path))
+                        .clone(),
+                    Err(_) => InternalPathRules::default(),
+                };
+
+                let path_rules_signature = path_rules.signature();
+
+                // Check if we already have this entry cached, and if it is still relevant
+                match previous_entries.remove(rel_path.as_str()) {
+                    Some(entry)
+                        if entry.last_modified == last_modified
+                            && entry.sidecar_last_modified == sidecar_last_modified
+                            && entry.replaces_vanilla == replaces_vanilla
+                            && entry.path_rules_signature == path_rules_signature =>
+                    {
+                        return ModdedBundle { rel_path, entry, dependencies: Vec::new(), path_rules };
+                    },
+                    // The timestamp doesn't match, we'll have to scrub the entry
+                    Some(_) => println!("Timestamp does not match, invalidating entry"),
+                    None => println!("File not found in cache"),
                 }
 
-                let bundle_file = manager
-                    .get_file(&rel_path)
-                    .unwrap_or_else(|err| panic!("Couldn't read bundle `{}`: {err}", rel_path));
+                uncached.push(idx);
 
-    
-                let bundle = astra_formats::Bundle::from_slice(&bundle_file)
-                    .unwrap_or_else(|err| panic!("Astra-formats couldn't parse bundle `{}`: {err}", rel_path));
-
-                // Only look for the first AssetFile, as we do not care about the raw sections 
-                let asset = bundle
-                    .files()
-                    .filter_map(|(_, file)| match file {
-                        astra_formats::BundleFile::Assets(asset) => Some(asset),
-                        _ => None,
-                    })
-                    .next()
-                    .unwrap_or_else(|| panic!("Could not find an Asset section in bundle '{rel_path}'"));
-
-                // Find the AssetBundle asset in the file.
-                let assetbundle = asset.assets
-                    .iter()
-                    .filter_map(|asset| {
-                        match asset {
-                            Asset::Bundle(bundle) => Some(bundle),
-                            _ => None
+                let entry = CachedCatalogEntry {
+                    internal_id: internal_id.to_string(),
+                    primary_key: primary_key.to_string(),
+                    replaces_vanilla,
+                    last_modified,
+                    sidecar_last_modified,
+                    path_rules_signature,
+                    ..Default::default()
+                };
+
+                ModdedBundle { rel_path, entry, dependencies: Vec::new(), path_rules }
+            })
+            .collect();
+
+        println!("Discovering {} bundles took {}ms", bundles.len(), discovery_timer.elapsed().as_millis());
+
+        // Timer for the process of reading the bundles that aren't cached
+        let inspection_timer = std::time::Instant::now();
+
+        let results = pool::run_jobs(
+            &uncached,
+            WORKER_COUNT,
+            INSPECTION_MEMORY_LIMIT,
+            |idx| manager.get_file_size(&bundles[*idx].rel_path).unwrap_or(0),
+            |idx| inspect_bundle(&bundles[*idx].rel_path, &bundles[*idx].path_rules),
+        );
+
+        println!("Inspecting {} bundles took {}ms", uncached.len(), inspection_timer.elapsed().as_millis());
+
+        let mut results: HashMap<usize, Result<InspectedBundle, String>> = uncached.into_iter().zip(results).collect();
+
+        // Merge the results in path order, so the Catalog ends up the same no matter which worker finished first
+        let bundles: Vec<ModdedBundle> = bundles
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, mut bundle)| match results.remove(&idx) {
+                Some(Ok(inspected)) => {
+                    bundle.entry.cab_name = inspected.cab_name;
+                    bundle.entry.entries = inspected
+                        .entries
+                        .into_iter()
+                        .map(|(mut asset, kind)| {
+                            asset.asset_type = kind.map_or(MISSING_ASSET_TYPE, |kind| provider_types.get(kind));
+                            asset
+                        })
+                        .collect();
+                    bundle.entry.externals = inspected.externals;
+                    cache_modified = true;
+                    Some(bundle)
+                },
+                Some(Err(reason)) => {
+                    report_bundle_error(&bundle.rel_path, reason);
+                    cache_modified = true;
+                    None
+                },
+                // Cached
+                None => Some(bundle),
+            })
+            .collect();
+
+        // Timer for the process of looking for bundles that can't coexist
+        let collision_timer = std::time::Instant::now();
+
+        // Bundles sharing a CAB name or a container path would make the Catalog or Unity fail later on, so leave them out before anything is added
+        let mut collisions: HashMap<usize, CollisionError> = {
+            let mod_names: Vec<String> = bundles.iter().map(|bundle| get_mod_name(&bundle.rel_path)).collect();
+
+            let identities: Vec<BundleIdentity> = bundles
+                .iter()
+                .zip(mod_names.iter())
+                .map(|(bundle, mod_name)| BundleIdentity {
+                    mod_name,
+                    path: bundle.rel_path.as_str(),
+                    cab_name: &bundle.entry.cab_name,
+                    replaces_vanilla: bundle.entry.replaces_vanilla,
+                    containers: bundle.entry.entries.iter().map(|asset| asset.container_internal_id.as_str()).collect(),
+                })
+                .collect();
+
+            find_collisions(&identities, &lut_cache).into_iter().collect()
+        };
+
+        let bundle_count = bundles.len();
+
+        let bundles: Vec<ModdedBundle> = bundles
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, bundle)| match collisions.remove(&idx) {
+                Some(collision) => {
+                    report_bundle_error(&bundle.rel_path, collision);
+                    cache_modified = true;
+                    None
+                },
+                None => Some(bundle),
+            })
+            .collect();
+
+        println!("Checking {} bundles for collisions took {}ms", bundle_count, collision_timer.elapsed().as_millis());
+
+        // Timer for the process of resolving dependencies and adding the bundles to the Catalog
+        let insertion_timer = std::time::Instant::now();
+
+        // Relative path of the bundles that were left out, by CAB name, to explain why the bundles depending on them are left out too
+        let mut left_out: HashMap<String, Utf8PathBuf> = HashMap::new();
+
+        // Bundles the Catalog would refuse are left out before anything gets to depend on them
+        let mut bundles: Vec<ModdedBundle> = bundles
+            .into_iter()
+            .filter_map(|bundle| {
+                // Replacements are already in the Catalog
+                if bundle.entry.replaces_vanilla {
+                    return Some(bundle);
+                }
+
+                match check_conflicts(&catalog, &bundle.entry) {
+                    Ok(()) => Some(bundle),
+                    // Leave the entry out of the cache so the bundle is inspected again on the next boot
+                    Err(reason) => {
+                        report_bundle_error(&bundle.rel_path, reason);
+                        left_out.insert(bundle.entry.cab_name, bundle.rel_path);
+                        cache_modified = true;
+                        None
+                    },
+                }
+            })
+            .collect();
+
+        // CAB names of the modded bundles, so they can be depended on like vanilla ones
+        let mut modded_cabs: HashMap<String, String> = bundles
+            .iter()
+            .map(|bundle| (bundle.entry.cab_name.to_owned(), bundle.entry.internal_id.to_owned()))
+            .collect();
+
+        // Cached bundles are resolved too, in case a bundle they depend on was removed since.
+        // Leaving a bundle out can break the ones depending on it, so keep going until every remaining bundle resolves.
+        loop {
+            let mut unresolved: HashMap<usize, DependencyError> = {
+                let resolver = DependencyResolver::new(&lut_cache, &modded_cabs);
+
+                bundles
+                    .iter_mut()
+                    .enumerate()
+                    .filter_map(|(idx, bundle)| {
+                        let externals = bundle.entry.externals.iter().map(String::as_str);
+
+                        match resolver.resolve_all(&bundle.entry.internal_id, externals) {
+                            Ok(dependencies) => {
+                                bundle.dependencies = dependencies;
+                                None
+                            },
+                            Err(err) => Some((idx, err)),
                         }
                     })
-                    .next()
-                    // This technically can't ever happen, but modders be modders.
-                    .unwrap_or_else(|| panic!("Could not find AssetBundle entry in bundle `{rel_path}`"));
-
-                let mut dependencies: Vec<String> = asset
-                    .externals
-                    .iter()
-                    .map(|external| {
-                        // TODO: Handle this better, because Library:/ entries exist too and would crash this code.
-                        let path = external.path.to_string();
-                        // println!("Dependency Path: {}", path);
-
-                        if path.starts_with("archive") {
-                            // let path = path.strip_prefix("archive:/").unwrap();
-                            let cab = path[9..36+9].to_lowercase();
-                            lut_cache.get(&cab).unwrap_or_else(|| panic!("Bundle `{rel_path}` has a dependency that is Addressable but does not exist in the original game files.")).to_owned()
-                        } else {
-                            // Deal with the Library here
-                            // cache.get(&path.to_lowercase()).unwrap().to_owned()
-                            path.to_lowercase()
+                    .collect()
+            };
+
+            if unresolved.is_empty() {
+                break;
+            }
+
+            bundles = bundles
+                .into_iter()
+                .enumerate()
+                .filter_map(|(idx, bundle)| match unresolved.remove(&idx) {
+                    Some(err) => {
+                        match &err {
+                            DependencyError::MissingBundle(cab) if left_out.contains_key(cab) => report_bundle_error(
+                                &bundle.rel_path,
+                                format!("dependency `{}` could not be added to the catalog", left_out[cab]),
+                            ),
+                            _ => report_bundle_error(&bundle.rel_path, err),
                         }
-                    })
-                    .collect();
 
-                // Add the file as its own dependency, as expected for the Catalog.
-                dependencies.insert(0, internal_id.to_string());
-    
-                // println!("Dependencies: {:#?}", dependencies);
-                
-                // Grab the last entry because it better represents the type we're seeking (like Texture2D -> Sprite)
-                let (container_internal_id, assetinfo) = assetbundle.container_map.last().unwrap();
-    
-                // This will supposedly help support file addition for every type of bundle more easily than checking for specific paths.
-                let asset_type = if let Some(found_asset) = asset.get_asset_by_path_id(assetinfo.asset.path_id) {
-                    match found_asset {
-                        Asset::Texture2D(_, _) => 1,
-                        Asset::Sprite(_) => 2,
-                        Asset::Text(_) => 12,
-                        Asset::Terrain(_) => 13,
-                        Asset::Unparsed(_) => {
-                            // We handle any type that isn't parsed by Astra-formats here
-                            match found_asset.type_hash() {
-                                // AnimationClip
-                                -80937412517696055409803870673809846754 => 36,
-                                _ => 4
-                            }
-                        },
-                        _ => 4,
+                        modded_cabs.remove(&bundle.entry.cab_name);
+                        left_out.insert(bundle.entry.cab_name.to_owned(), bundle.rel_path.to_owned());
+
+                        // The bundle itself is fine, so keep it cached for when the missing bundle comes back
+                        cache.entries.insert(bundle.rel_path.to_string(), bundle.entry);
+                        None
+                    },
+                    None => Some(bundle),
+                })
+                .collect();
+        }
+
+        // InternalIds of the bundles that failed to be added despite the checks, so nothing ends up depending on them
+        let mut failed: Vec<String> = Vec::new();
+
+        let mut replaced: Vec<ReplacedAssets> = Vec::new();
+
+        for bundle in bundles {
+            // println!("Dependencies: {:#?}", bundle.dependencies);
+
+            let entry = &bundle.entry;
+            let dependencies = &bundle.dependencies;
+
+            let result = if let Some(dependency) = dependencies.iter().find(|dependency| failed.contains(dependency)) {
+                Err(format!("dependency `{}` could not be added to the catalog", dependency))
+            } else if entry.replaces_vanilla {
+                add_new_assets(&mut catalog, entry, dependencies).map(|internal_ids| {
+                    if !internal_ids.is_empty() {
+                        replaced.push(ReplacedAssets { rel_path: bundle.rel_path.to_owned(), internal_ids, dependencies: dependencies.to_owned() });
                     }
-                } else {
-                    10
-                };
-    
-                // println!("Prefab InternalId: {}, type: {}", internal_id, asset_type);
-
-                let prefixes = [
-                    "Assets/Share/Addressables/",
-                    "Assets/Project/Addressables/",
-                    "Assets/Share/Scenes/Map/",
-                    "Patch/Patch0/",
-                    "Patch/Patch1/",
-                    "Patch/Patch2/",
-                    "Patch/Patch3/",
-                ];
-
-                let internal_path = prefixes.iter().fold(container_internal_id.as_str(), |path, prefix| {
-                    path.trim_start_matches(prefix)
-                });
-
-                let suffixes = [
-                    ".prefab",
-                    ".png",
-                    ".asset",
-                    ".unity",
-                    ".fbx",
-                ];
-
-                let internal_path = suffixes.iter().fold(internal_path, |path, suffix| {
-                    path.trim_end_matches(suffix)
-                });
-    
-                // println!("Trimmed InternalId: {}", internal_path);
-    
-                if let Err(err) = catalog.add_prefab(asset_type, container_internal_id.as_str().to_owned(), internal_path.to_owned(), &dependencies) {
-                    print_catalog_error(container_internal_id.as_str(), err)
-                }
+                })
+            } else {
+                // Add the bundle to the Catalog, followed by every one of its assets
+                catalog
+                    .add_bundle(&entry.internal_id, &entry.primary_key, extra.clone())
+                    .map_err(|err| describe_catalog_error(&entry.internal_id, err))
+                    .and_then(|_| {
+                        for asset in entry.entries.iter() {
+                            catalog
+                                .add_prefab(asset.asset_type, asset.container_internal_id.clone(), asset.internal_path.clone(), dependencies)
+                                .map_err(|err| describe_catalog_error(&asset.container_internal_id, err))?;
+                        }
+
+                        Ok(())
+                    })
+            };
 
+            match result {
                 // Write entry to cache
+                Ok(_) => {
+                    cache.entries.insert(bundle.rel_path.to_string(), bundle.entry);
+                },
+                // Leave the entry out of the cache so the bundle is inspected again on the next boot
+                Err(reason) => {
+                    report_bundle_error(&bundle.rel_path, reason);
+                    failed.push(bundle.entry.internal_id);
+                    cache_modified = true;
+                },
+            }
+        }
 
-                let entry = cache.entry(rel_path.to_string()).or_insert(CachedCatalogEntry::default());
-                entry.internal_id = internal_id.as_str().to_string();
-                entry.container_internal_id = container_internal_id.as_str().to_string();
-                entry.primary_key = primary_key.to_string();
-                entry.internal_path = internal_path.to_string();
-                entry.dependencies = dependencies;
-                entry.asset_type = asset_type;
-                entry.last_modified = manager.get_last_modified(rel_path).unwrap();
+        println!("Adding bundles to the catalog took {}ms", insertion_timer.elapsed().as_millis());
 
-                cache_modified = true;
-            } else {
-                println!("File already exists in the catalog, skipping");
+        // Timer for the process of serializing the Catalog back to JSON
+        let serialization_timer = std::time::Instant::now();
+
+        let new_catalog = if replaced.is_empty() {
+            serde_json::to_string(&catalog).unwrap()
+        } else {
+            let mut serialized = serde_json::to_value(&catalog).unwrap();
+
+            match ContentCatalogData::from_json(&serialized) {
+                Ok(mut content) => {
+                    for replacement in replaced {
+                        if let Err(reason) = update_replaced_dependencies(&mut content, &replacement) {
+                            report_bundle_error(&replacement.rel_path, reason);
+                            // Inspect the bundle again on the next boot
+                            cache.entries.remove(replacement.rel_path.as_str());
+                            cache_modified = true;
+                        }
+                    }
+
+                    content.write_to(&mut serialized);
+                },
+                Err(err) => println!("Could not update the dependencies of replaced assets: {}", err),
             }
-        });
-        
-        println!("Processing bundles took {}ms", bundle_timer.elapsed().as_millis());
 
-        let new_catalog = serde_json::to_string(&catalog).unwrap().into();
+            serialized.to_string()
+        }
+        .into();
+
+        println!("Serializing the catalog took {}ms", serialization_timer.elapsed().as_millis());
         println!("Catalog patching took {}ms", timer.elapsed().as_millis());
 
+        if !previous_entries.is_empty() {
+            println!("Pruned {} catalog cache entries of bundles that no longer exist", previous_entries.len());
+            cache_modified = true;
+        }
+
         if cache_modified {
-            let out_cache = miniserde::json::to_string(&cache);
-            std::fs::write("sd:/engage/catalog.lut", out_cache).unwrap();
-            println!("Wrote catalog cache to SD");
+            match cache.save(CATALOG_CACHE_PATH) {
+                Ok(()) => println!("Wrote catalog cache to SD"),
+                Err(err) => println!("Could not write the catalog cache to SD: {}", err),
+            }
+        }
+
+        if let Err(err) = write_report() {
+            println!("Could not write the catalog error report to SD: {}", err);
         }
 
 
@@ -257,12 +479,172 @@ pub fn from_json_hook(json: &Il2CppString, method_info: OptionalMethod) -> *cons
     }
 }
 
-fn print_catalog_error(internal_id: &str, err: CatalogError) {
+/// What was found in a modded bundle that wasn't cached.
+struct InspectedBundle {
+    cab_name: String,
+    /// The kind of every asset, or `None` if it is missing from the bundle. Their provider type is set once every worker is done.
+    entries: Vec<(CachedAssetEntry, Option<AssetKind>)>,
+    externals: Vec<String>,
+}
+
+/// Read a modded bundle to find its CAB name, externals and addressable assets.
+///
+/// This runs on the catalog workers, so it must not touch the Catalog.
+fn inspect_bundle(rel_path: &Utf8Path, path_rules: &InternalPathRules) -> Result<InspectedBundle, String> {
+    let sidecar = BundleSidecar::load(rel_path);
+
+    let mut path_rules = path_rules.clone();
+    path_rules.extend(&sidecar.prefixes, &sidecar.suffixes);
+
+    let bundle_file = mods::manager::Manager::get()
+        .get_file(rel_path)
+        .map_err(|err| format!("the file could not be read: {}", err))?;
+
+    let bundle = astra_formats::Bundle::from_slice(&bundle_file)
+        .map_err(|err| format!("the file is not a valid AssetBundle: {}", err))?;
+
+    // Only look for the first AssetFile, as we do not care about the raw sections 
+    let (cab_name, asset) = bundle
+        .files()
+        .filter_map(|(name, file)| match file {
+            astra_formats::BundleFile::Assets(asset) => Some((name.to_lowercase(), asset)),
+            _ => None,
+        })
+        .next()
+        .ok_or("there is no Asset section in the bundle")?;
+
+    // Astra-formats only tells apart the types it parses, so the kinds of the assets come from the class IDs Unity wrote when possible
+    let class_ids = read_class_ids(&bundle_file, &cab_name).unwrap_or_else(|err| {
+        println!("Could not read the class IDs of '{}', guessing the asset types instead: {}", rel_path, err);
+        HashMap::new()
+    });
+
+    // Find the AssetBundle asset in the file.
+    let assetbundle = asset.assets
+        .iter()
+        .filter_map(|asset| {
+            match asset {
+                Asset::Bundle(bundle) => Some(bundle),
+                _ => None
+            }
+        })
+        .next()
+        // This technically can't ever happen, but modders be modders.
+        .ok_or("there is no AssetBundle entry in the bundle")?;
+
+    let externals: Vec<String> = asset
+        .externals
+        .iter()
+        .map(|external| external.path.to_string())
+        .collect();
+
+    let mut entries: Vec<(CachedAssetEntry, Option<AssetKind>)> = Vec::with_capacity(assetbundle.container_map.len());
+
+    for (container_internal_id, assetinfo) in assetbundle.container_map.iter() {
+        // Assets are registered depending on their type, unless the sidecar of the bundle says otherwise
+        let kind = match sidecar.asset_types.get(container_internal_id.as_str()) {
+            Some(kind) => Some(*kind),
+            None => match class_ids.get(&assetinfo.asset.path_id) {
+                Some(class_id) => Some(AssetKind::from_class_id(*class_id)),
+                None => asset.get_asset_by_path_id(assetinfo.asset.path_id).map(AssetKind::from_asset),
+            },
+        };
+
+        let asset_entry = CachedAssetEntry {
+            container_internal_id: container_internal_id.as_str().to_owned(),
+            internal_path: sidecar
+                .internal_paths
+                .get(container_internal_id.as_str())
+                .map_or_else(|| path_rules.apply(container_internal_id.as_str()).to_owned(), String::to_owned),
+            asset_type: MISSING_ASSET_TYPE,
+        };
+
+        // Assets sharing a container path (like a Texture2D and its Sprite) are a single entry in the Catalog.
+        // Keep the last one because it better represents the type we're seeking.
+        match entries.iter_mut().find(|(entry, _)| entry.container_internal_id == asset_entry.container_internal_id) {
+            Some(entry) => *entry = (asset_entry, kind),
+            None => entries.push((asset_entry, kind)),
+        }
+    }
+
+    if let Some(internal_path) = sidecar.internal_path {
+        match entries.as_mut_slice() {
+            [(entry, _)] => entry.internal_path = internal_path,
+            _ => return Err(format!("the sidecar sets `internal_path` but the bundle has {} assets, use `internal_paths` instead", entries.len())),
+        }
+    }
+
+    Ok(InspectedBundle { cab_name, entries, externals })
+}
+
+/// Add the assets of a replacement bundle the vanilla one didn't have, like for any other modded bundle.
+///
+/// Returns the InternalIds of the assets the Catalog already has, since the modded file can need more bundles than the vanilla one.
+fn add_new_assets(catalog: &mut Catalog, entry: &CachedCatalogEntry, dependencies: &[String]) -> Result<Vec<String>, String> {
+    let mut replaced = Vec::new();
+
+    for asset in entry.entries.iter() {
+        if catalog.get_internal_id_index(&asset.container_internal_id).is_some() {
+            replaced.push(asset.container_internal_id.to_owned());
+        } else {
+            catalog
+                .add_prefab(asset.asset_type, asset.container_internal_id.clone(), asset.internal_path.clone(), dependencies)
+                .map_err(|err| describe_catalog_error(&asset.container_internal_id, err))?;
+        }
+    }
+
+    Ok(replaced)
+}
+
+/// Add the dependencies of the modded bundle to the ones the vanilla assets already have.
+fn update_replaced_dependencies(content: &mut ContentCatalogData, replacement: &ReplacedAssets) -> Result<(), String> {
+    for internal_id in replacement.internal_ids.iter() {
+        let vanilla = content
+            .get_dependencies(internal_id)
+            .map_err(|err| format!("the dependencies of replaced asset `{}` could not be read: {}", internal_id, err))?;
+
+        let merged = merge_dependencies(&vanilla, &replacement.dependencies);
+
+        if merged.len() != vanilla.len() {
+            println!("Adding {} dependencies to replaced asset `{}`", merged.len() - vanilla.len(), internal_id);
+
+            content
+                .set_dependencies(internal_id, &merged)
+                .map_err(|err| format!("the dependencies of replaced asset `{}` could not be updated: {}", internal_id, err))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Make sure none of the assets of a new bundle are already in the Catalog, so it is either added entirely or not at all.
+fn check_conflicts(catalog: &Catalog, entry: &CachedCatalogEntry) -> Result<(), String> {
+    match entry.entries.iter().find(|asset| catalog.get_internal_id_index(&asset.container_internal_id).is_some()) {
+        Some(asset) => Err(describe_catalog_error(&asset.container_internal_id, CatalogError::DuplicateInternalId)),
+        None => Ok(()),
+    }
+}
+
+/// Get the internal path rules of the game, extended with the ones of the mod providing a file.
+fn get_mod_path_rules(rel_path: &Utf8Path) -> InternalPathRules {
+    let mut path_rules = InternalPathRules::default();
+
+    if let Ok(config) = mods::manager::Manager::get().get_mod_config(rel_path) {
+        path_rules.extend(&config.addressables().prefixes, &config.addressables().suffixes);
+    }
+
+    path_rules
+}
+
+fn describe_catalog_error(internal_id: &str, err: CatalogError) -> String {
     match err {
-        CatalogError::Io(io) => panic!("A file related issue happened: {}", io),
-        CatalogError::Json(json) => panic!("A json related error happened: {}", json),
-        CatalogError::Base64Decode(_) => panic!("Your catalog.bundle file is malformed. Delete it and restart the game."),
-        CatalogError::DuplicateInternalId => panic!("InternalId `{}` already exists in catalog.bundle.\nThis can happen if you have a bundle with the same container path as an existing one.", internal_id),
-        CatalogError::MissingInternalId => panic!("The following InternalId appears to be missing: {}", internal_id),
+        CatalogError::Io(io) => format!("a file related issue happened: {}", io),
+        CatalogError::Json(json) => format!("a json related error happened: {}", json),
+        CatalogError::Base64Decode(_) => String::from("the catalog.bundle file is malformed"),
+        CatalogError::DuplicateInternalId => format!(
+            "InternalId `{}` already exists in catalog.bundle. This can happen if you have a bundle with the same container path as an existing one",
+            internal_id
+        ),
+        CatalogError::MissingInternalId => format!("InternalId `{}` appears to be missing", internal_id),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,205 @@
+use astra_formats::Asset;
+use serde::Deserialize;
+
+/// Provider type registered for assets that could not be found in their bundle.
+pub const MISSING_ASSET_TYPE: i32 = 10;
+
+/// Type hash of the AnimationClips, which Astra-formats does not parse.
+const ANIMATION_CLIP_TYPE_HASH: i128 = -80937412517696055409803870673809846754;
+
+/// Kinds of assets a modded bundle can provide, named after their Unity type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum AssetKind {
+    GameObject,
+    Texture2D,
+    Sprite,
+    TextAsset,
+    TerrainData,
+    AnimationClip,
+    RuntimeAnimatorController,
+    Material,
+    Mesh,
+    AudioClip,
+    Font,
+    Shader,
+    ScriptableObject,
+}
+
+/// Every kind of asset, with the Unity class IDs of the assets registered as that kind, the name of its type in the Catalog,
+/// and its index in the vanilla Catalog for the types whose index is known ahead of time.
+const ASSET_TYPES: [(AssetKind, &[i32], &str, Option<i32>); 13] = [
+    (AssetKind::GameObject, &[1], "UnityEngine.GameObject", Some(4)),
+    (AssetKind::Texture2D, &[28], "UnityEngine.Texture2D", Some(1)),
+    (AssetKind::Sprite, &[213], "UnityEngine.Sprite", Some(2)),
+    (AssetKind::TextAsset, &[49], "UnityEngine.TextAsset", Some(12)),
+    (AssetKind::TerrainData, &[156], "UnityEngine.TerrainData", Some(13)),
+    (AssetKind::AnimationClip, &[74], "UnityEngine.AnimationClip", Some(36)),
+    // AnimatorController and AnimatorOverrideController
+    (AssetKind::RuntimeAnimatorController, &[91, 221], "UnityEngine.RuntimeAnimatorController", None),
+    (AssetKind::Material, &[21], "UnityEngine.Material", None),
+    (AssetKind::Mesh, &[43], "UnityEngine.Mesh", None),
+    (AssetKind::AudioClip, &[83], "UnityEngine.AudioClip", None),
+    (AssetKind::Font, &[128], "UnityEngine.Font", None),
+    (AssetKind::Shader, &[48], "UnityEngine.Shader", None),
+    // Every ScriptableObject is serialized as a MonoBehaviour
+    (AssetKind::ScriptableObject, &[114], "UnityEngine.ScriptableObject", None),
+];
+
+impl AssetKind {
+    /// Find the kind of an asset from what Astra-formats parsed it as, defaulting to [`AssetKind::GameObject`] like prefabs.
+    ///
+    /// Only used when the class IDs of a bundle can't be read, since only the kinds Astra-formats tells apart are detected.
+    pub fn from_asset(asset: &Asset) -> Self {
+        match asset {
+            Asset::Texture2D(_, _) => AssetKind::Texture2D,
+            Asset::Sprite(_) => AssetKind::Sprite,
+            Asset::Text(_) => AssetKind::TextAsset,
+            Asset::Terrain(_) => AssetKind::TerrainData,
+            // We handle any type that isn't parsed by Astra-formats here
+            Asset::Unparsed(_) => Self::from_type_hash(asset.type_hash()),
+            _ => AssetKind::GameObject,
+        }
+    }
+
+    /// Find the kind of an asset Astra-formats does not parse from its type hash.
+    fn from_type_hash(type_hash: i128) -> Self {
+        match type_hash {
+            ANIMATION_CLIP_TYPE_HASH => AssetKind::AnimationClip,
+            _ => AssetKind::GameObject,
+        }
+    }
+
+    /// Find the kind of an asset from its Unity class ID, defaulting to [`AssetKind::GameObject`] like prefabs.
+    pub fn from_class_id(class_id: i32) -> Self {
+        ASSET_TYPES
+            .iter()
+            .find(|(_, class_ids, _, _)| class_ids.contains(&class_id))
+            .map_or(AssetKind::GameObject, |(kind, _, _, _)| *kind)
+    }
+
+    /// Name of the type in the resource types of the Catalog.
+    pub fn type_name(self) -> &'static str {
+        ASSET_TYPES.iter().find(|(kind, _, _, _)| *kind == self).map(|(_, _, type_name, _)| *type_name).unwrap()
+    }
+}
+
+/// Provider type to register every kind of asset with in the Catalog.
+pub struct ProviderTypes<'a> {
+    /// Reads the class names of the resource types of the Catalog, which is only needed for the kinds without a known index.
+    read_resource_types: Box<dyn FnMut() -> Vec<String> + 'a>,
+    resource_types: Option<Vec<String>>,
+}
+
+impl<'a> ProviderTypes<'a> {
+    pub fn new(read_resource_types: impl FnMut() -> Vec<String> + 'a) -> Self {
+        Self { read_resource_types: Box::new(read_resource_types), resource_types: None }
+    }
+
+    /// Kinds the Catalog has no provider for are registered as GameObjects, which is what the game does for anything it doesn't know about.
+    pub fn get(&mut self, kind: AssetKind) -> i32 {
+        let (_, _, type_name, provider_type) = ASSET_TYPES.iter().find(|(asset_kind, _, _, _)| *asset_kind == kind).unwrap();
+
+        if let Some(provider_type) = provider_type {
+            return *provider_type;
+        }
+
+        let resource_types = self.resource_types.get_or_insert_with(&mut self.read_resource_types);
+
+        match resource_types.iter().position(|class_name| class_name == type_name) {
+            Some(idx) => idx as i32,
+            None => {
+                println!("The Catalog has no provider for {}, registering it as a GameObject", type_name);
+                self.get(AssetKind::GameObject)
+            },
+        }
+    }
+}
+
+/// Read the class names of the `m_resourceTypes` list of the Catalog JSON.
+///
+/// Only that list is deserialized, the rest of the Catalog is never looked at.
+pub fn read_resource_types(json: &str) -> Vec<String> {
+    let resource_types = json
+        .find("\"m_resourceTypes\"")
+        .and_then(|idx| json[idx..].find(':').map(|colon| &json[idx + colon + 1..]))
+        .ok_or_else(|| String::from("there is no `m_resourceTypes` in the Catalog"))
+        .and_then(|list| {
+            let mut deserializer = serde_json::Deserializer::from_str(list);
+            Vec::<CatalogResourceType>::deserialize(&mut deserializer).map_err(|err| err.to_string())
+        });
+
+    match resource_types {
+        Ok(resource_types) => resource_types.into_iter().map(|resource_type| resource_type.class_name).collect(),
+        Err(err) => {
+            println!("Could not read the resource types of the Catalog: {}", err);
+            Vec::new()
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct CatalogResourceType {
+    #[serde(rename = "m_ClassName")]
+    class_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_resource_types, AssetKind, ProviderTypes, ANIMATION_CLIP_TYPE_HASH};
+
+    #[test]
+    fn class_ids_are_mapped_to_kinds() {
+        assert_eq!(AssetKind::from_class_id(28), AssetKind::Texture2D);
+        assert_eq!(AssetKind::from_class_id(221), AssetKind::RuntimeAnimatorController);
+        assert_eq!(AssetKind::from_class_id(114), AssetKind::ScriptableObject);
+        // Unknown types are treated like prefabs
+        assert_eq!(AssetKind::from_class_id(1000), AssetKind::GameObject);
+    }
+
+    #[test]
+    fn unparsed_assets_are_mapped_by_type_hash() {
+        assert_eq!(AssetKind::from_type_hash(ANIMATION_CLIP_TYPE_HASH), AssetKind::AnimationClip);
+        assert_eq!(AssetKind::from_type_hash(0), AssetKind::GameObject);
+    }
+
+    #[test]
+    fn provider_types_are_read_from_the_catalog() {
+        let json = r#"{
+            "m_LocatorId": "AddressablesMainContentCatalog",
+            "m_resourceTypes" : [
+                { "m_AssemblyName": "UnityEngine.CoreModule", "m_ClassName": "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource" },
+                { "m_AssemblyName": "UnityEngine.CoreModule", "m_ClassName": "UnityEngine.Texture2D" },
+                { "m_AssemblyName": "UnityEngine.CoreModule", "m_ClassName": "UnityEngine.Material" }
+            ],
+            "m_InternalIds": []
+        }"#;
+
+        assert_eq!(
+            read_resource_types(json),
+            [
+                "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource",
+                "UnityEngine.Texture2D",
+                "UnityEngine.Material"
+            ]
+        );
+        assert!(read_resource_types("{}").is_empty());
+    }
+
+    #[test]
+    fn resource_types_are_only_read_for_unknown_indices() {
+        let mut reads = 0;
+
+        let mut provider_types = ProviderTypes::new(|| {
+            reads += 1;
+            vec![String::from("UnityEngine.GameObject"), String::from("UnityEngine.Material")]
+        });
+
+        // Known indices are kept no matter what the Catalog says
+        assert_eq!(provider_types.get(AssetKind::Sprite), 2);
+        assert_eq!(provider_types.get(AssetKind::Material), 1);
+        assert_eq!(provider_types.get(AssetKind::Mesh), provider_types.get(AssetKind::GameObject));
+
+        drop(provider_types);
+        assert_eq!(reads, 1);
+    }
+}
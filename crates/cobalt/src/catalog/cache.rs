@@ -0,0 +1,87 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::Hasher,
+    io,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::CachedCatalogEntry;
+use crate::utils::env::get_cobalt_version;
+
+/// Catalog cache of the modded bundles that exist on the SD, so they don't have to be read on every boot.
+#[derive(miniserde::Serialize, miniserde::Deserialize, Default)]
+pub struct CatalogCache {
+    /// Version of Cobalt that wrote the cache, since the format of the entries can change between releases.
+    cobalt_version: String,
+    /// Hash of the cache.lut the dependencies were resolved with.
+    lut_hash: u64,
+    /// Relative path of every bundle to its entry.
+    pub entries: HashMap<String, CachedCatalogEntry>,
+}
+
+impl CatalogCache {
+    pub fn new(lut_hash: u64) -> Self {
+        Self {
+            cobalt_version: get_cobalt_version().to_owned(),
+            lut_hash,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Read the cache at `path`, or start a new one if it is missing, unreadable or was written for something else than the current Cobalt and cache.lut.
+    pub fn load(path: impl AsRef<Utf8Path>, lut_hash: u64) -> Self {
+        let path = path.as_ref();
+
+        // If the game was closed in the middle of a save, the previous cache is gone but the new one is complete
+        let content = std::fs::read_to_string(path).or_else(|_| std::fs::read_to_string(temp_path(path)));
+
+        let cache: CatalogCache = match content {
+            Ok(content) => match miniserde::json::from_str(&content) {
+                Ok(cache) => cache,
+                Err(_) => {
+                    println!("Catalog cache is malformed or uses an older format, rebuilding it");
+                    return Self::new(lut_hash);
+                },
+            },
+            Err(_) => return Self::new(lut_hash),
+        };
+
+        if cache.cobalt_version != get_cobalt_version() {
+            println!("Catalog cache was written by Cobalt {}, rebuilding it", cache.cobalt_version);
+            Self::new(lut_hash)
+        } else if cache.lut_hash != lut_hash {
+            println!("cache.lut changed since the catalog cache was written, rebuilding it");
+            Self::new(lut_hash)
+        } else {
+            cache
+        }
+    }
+
+    /// Write the cache to a temporary file first and then move it over the previous one, so a crash cannot leave a half-written cache behind.
+    pub fn save(&self, path: impl AsRef<Utf8Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temp = temp_path(path);
+
+        std::fs::write(&temp, miniserde::json::to_string(self))?;
+
+        // The Switch's filesystem refuses to rename over an existing file
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+
+        std::fs::rename(temp, path)
+    }
+}
+
+/// Hash the content of cache.lut, to know when the cached dependencies have to be resolved again.
+pub fn hash_lut(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(content.as_bytes());
+    hasher.finish()
+}
+
+fn temp_path(path: &Utf8Path) -> Utf8PathBuf {
+    path.with_extension("lut.tmp")
+}
@@ -0,0 +1,461 @@
+use std::{collections::HashMap, fmt};
+
+const UNITYFS_SIGNATURE: &[u8] = b"UnityFS\0";
+
+/// The blocks info is at the end of the bundle instead of after the header.
+const BLOCKS_INFO_AT_THE_END: u32 = 0x80;
+/// The blocks start on a 16 bytes boundary after the blocks info.
+const BLOCKS_INFO_NEEDS_PADDING: u32 = 0x200;
+const COMPRESSION_MASK: u32 = 0x3F;
+
+/// Class ID of MonoBehaviours, whose types are followed by the hash of their script.
+const MONO_BEHAVIOUR_CLASS_ID: i32 = 114;
+
+/// Oldest SerializedFile version we can read. Engage's bundles use a much more recent one.
+const MIN_SERIALIZED_FILE_VERSION: u32 = 14;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassIdError {
+    /// The file ended before everything could be read.
+    Truncated,
+    NotUnityFs,
+    /// The data uses a compression we can't decompress, like LZMA.
+    UnsupportedCompression(u32),
+    UnsupportedVersion(u32),
+    Decompression(String),
+    /// The bundle has no file with this name.
+    MissingFile(String),
+}
+
+impl fmt::Display for ClassIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassIdError::Truncated => write!(f, "the file is truncated"),
+            ClassIdError::NotUnityFs => write!(f, "the file is not a UnityFS AssetBundle"),
+            ClassIdError::UnsupportedCompression(compression) => write!(f, "compression type {} is not supported", compression),
+            ClassIdError::UnsupportedVersion(version) => write!(f, "SerializedFile version {} is not supported", version),
+            ClassIdError::Decompression(err) => write!(f, "a block could not be decompressed: {}", err),
+            ClassIdError::MissingFile(name) => write!(f, "there is no `{}` in the bundle", name),
+        }
+    }
+}
+
+/// Read the Unity class ID of every object of a file in an AssetBundle, by path ID.
+///
+/// Astra-formats only tells apart the types it parses, while the class ID is what Unity itself uses to know the type of an object.
+/// Only the blocks holding the metadata of the file are decompressed.
+pub fn read_class_ids(bundle: &[u8], file_name: &str) -> Result<HashMap<i64, i32>, ClassIdError> {
+    let mut reader = Reader::big_endian(bundle);
+
+    if reader.read_bytes(UNITYFS_SIGNATURE.len())? != UNITYFS_SIGNATURE {
+        return Err(ClassIdError::NotUnityFs);
+    }
+
+    let version = reader.read_u32()?;
+    let _unity_version = reader.read_cstring()?;
+    let _unity_revision = reader.read_cstring()?;
+    let _size = reader.read_i64()?;
+    let compressed_blocks_info_size = reader.read_u32()? as usize;
+    let uncompressed_blocks_info_size = reader.read_u32()? as usize;
+    let flags = reader.read_u32()?;
+
+    if version >= 7 {
+        reader.align(16);
+    }
+
+    let compressed_blocks_info = if flags & BLOCKS_INFO_AT_THE_END != 0 {
+        let start = bundle.len().checked_sub(compressed_blocks_info_size).ok_or(ClassIdError::Truncated)?;
+        &bundle[start..]
+    } else {
+        reader.read_bytes(compressed_blocks_info_size)?
+    };
+
+    if flags & BLOCKS_INFO_NEEDS_PADDING != 0 {
+        reader.align(16);
+    }
+
+    let blocks_info = decompress(compressed_blocks_info, uncompressed_blocks_info_size, flags & COMPRESSION_MASK)?;
+    let mut info = Reader::big_endian(&blocks_info);
+    let _hash = info.read_bytes(16)?;
+
+    let mut blocks = Vec::new();
+    let mut offset = reader.position;
+
+    for _ in 0..info.read_count()? {
+        let uncompressed_size = info.read_u32()? as usize;
+        let compressed_size = info.read_u32()? as usize;
+        let flags = info.read_u16()? as u32;
+
+        blocks.push(Block { offset, compressed_size, uncompressed_size, flags });
+        offset += compressed_size;
+    }
+
+    let mut node = None;
+
+    for _ in 0..info.read_count()? {
+        let offset = info.read_i64()?;
+        let size = info.read_i64()?;
+        let _flags = info.read_u32()?;
+        let path = info.read_cstring()?;
+
+        if node.is_none() && path.eq_ignore_ascii_case(file_name) {
+            node = Some((offset as usize, size as usize));
+        }
+    }
+
+    let (node_offset, node_size) = node.ok_or_else(|| ClassIdError::MissingFile(file_name.to_owned()))?;
+
+    let mut data = BlockData { bundle, blocks: blocks.into_iter(), data: Vec::new() };
+
+    // The header of the SerializedFile tells how big its metadata is
+    let (header_size, metadata_size, version, big_endian) = {
+        let mut header = Reader::big_endian(data.get(node_offset, 48.min(node_size))?);
+        let mut metadata_size = header.read_u32()? as usize;
+        let _file_size = header.read_u32()?;
+        let version = header.read_u32()?;
+        let _data_offset = header.read_u32()?;
+
+        if version < MIN_SERIALIZED_FILE_VERSION {
+            return Err(ClassIdError::UnsupportedVersion(version));
+        }
+
+        let big_endian = header.read_u8()? != 0;
+        let _reserved = header.read_bytes(3)?;
+
+        if version >= 22 {
+            metadata_size = header.read_u32()? as usize;
+            let _file_size = header.read_i64()?;
+            let _data_offset = header.read_i64()?;
+            let _unknown = header.read_i64()?;
+        }
+
+        (header.position, metadata_size, version, big_endian)
+    };
+
+    let file = data.get(node_offset, (header_size + metadata_size).min(node_size))?;
+
+    let mut reader = Reader { data: file, position: header_size, big_endian };
+    read_objects(&mut reader, version)
+}
+
+/// Read the types and objects of a SerializedFile, starting right after its header.
+fn read_objects(reader: &mut Reader, version: u32) -> Result<HashMap<i64, i32>, ClassIdError> {
+    let _unity_version = reader.read_cstring()?;
+    let _target_platform = reader.read_i32()?;
+    let enable_type_tree = reader.read_u8()? != 0;
+
+    let mut class_ids = Vec::new();
+
+    for _ in 0..reader.read_count()? {
+        let class_id = reader.read_i32()?;
+
+        if version >= 16 {
+            let _stripped = reader.read_u8()?;
+        }
+
+        if version >= 17 {
+            let _script_type_index = reader.read_i16()?;
+        }
+
+        if (version < 16 && class_id < 0) || (version >= 16 && class_id == MONO_BEHAVIOUR_CLASS_ID) {
+            let _script_id = reader.read_bytes(16)?;
+        }
+
+        let _old_type_hash = reader.read_bytes(16)?;
+
+        if enable_type_tree {
+            let node_count = reader.read_count()?;
+            let string_buffer_size = reader.read_count()?;
+            let node_size = if version >= 19 { 32 } else { 24 };
+            reader.read_bytes(node_count * node_size + string_buffer_size)?;
+
+            if version >= 21 {
+                let dependency_count = reader.read_count()?;
+                reader.read_bytes(dependency_count * 4)?;
+            }
+        }
+
+        class_ids.push(class_id);
+    }
+
+    let mut objects = HashMap::new();
+
+    for _ in 0..reader.read_count()? {
+        reader.align(4);
+
+        let path_id = reader.read_i64()?;
+
+        if version >= 22 {
+            let _byte_start = reader.read_i64()?;
+        } else {
+            let _byte_start = reader.read_u32()?;
+        }
+
+        let _byte_size = reader.read_u32()?;
+        let type_id = reader.read_i32()?;
+
+        let class_id = match version {
+            ..=15 => reader.read_u16()? as i32,
+            _ => *usize::try_from(type_id).ok().and_then(|type_id| class_ids.get(type_id)).ok_or(ClassIdError::Truncated)?,
+        };
+
+        if version < 17 {
+            let _script_type_index = reader.read_i16()?;
+        }
+
+        if version == 15 || version == 16 {
+            let _stripped = reader.read_u8()?;
+        }
+
+        objects.insert(path_id, class_id);
+    }
+
+    Ok(objects)
+}
+
+struct Block {
+    offset: usize,
+    compressed_size: usize,
+    uncompressed_size: usize,
+    flags: u32,
+}
+
+/// The uncompressed data of the blocks of a bundle, decompressed as it is needed.
+struct BlockData<'a> {
+    bundle: &'a [u8],
+    blocks: std::vec::IntoIter<Block>,
+    data: Vec<u8>,
+}
+
+impl BlockData<'_> {
+    fn get(&mut self, offset: usize, size: usize) -> Result<&[u8], ClassIdError> {
+        while self.data.len() < offset + size {
+            let block = self.blocks.next().ok_or(ClassIdError::Truncated)?;
+            let compressed = self.bundle.get(block.offset..block.offset + block.compressed_size).ok_or(ClassIdError::Truncated)?;
+
+            self.data.extend(decompress(compressed, block.uncompressed_size, block.flags & COMPRESSION_MASK)?);
+        }
+
+        Ok(&self.data[offset..offset + size])
+    }
+}
+
+fn decompress(data: &[u8], uncompressed_size: usize, compression: u32) -> Result<Vec<u8>, ClassIdError> {
+    match compression {
+        0 => Ok(data.to_vec()),
+        // LZ4 and LZ4HC
+        2 | 3 => lz4_flex::block::decompress(data, uncompressed_size).map_err(|err| ClassIdError::Decompression(err.to_string())),
+        _ => Err(ClassIdError::UnsupportedCompression(compression)),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn big_endian(data: &'a [u8]) -> Self {
+        Self { data, position: 0, big_endian: true }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ClassIdError> {
+        let bytes = self.data.get(self.position..self.position + len).ok_or(ClassIdError::Truncated)?;
+        self.position += len;
+
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], ClassIdError> {
+        let mut bytes: [u8; N] = self.read_bytes(N)?.try_into().unwrap();
+
+        if !self.big_endian {
+            bytes.reverse();
+        }
+
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ClassIdError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ClassIdError> {
+        self.read_array().map(u16::from_be_bytes)
+    }
+
+    fn read_i16(&mut self) -> Result<i16, ClassIdError> {
+        self.read_array().map(i16::from_be_bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ClassIdError> {
+        self.read_array().map(u32::from_be_bytes)
+    }
+
+    fn read_i32(&mut self) -> Result<i32, ClassIdError> {
+        self.read_array().map(i32::from_be_bytes)
+    }
+
+    fn read_i64(&mut self) -> Result<i64, ClassIdError> {
+        self.read_array().map(i64::from_be_bytes)
+    }
+
+    fn read_count(&mut self) -> Result<usize, ClassIdError> {
+        usize::try_from(self.read_i32()?).map_err(|_| ClassIdError::Truncated)
+    }
+
+    fn read_cstring(&mut self) -> Result<&'a str, ClassIdError> {
+        let len = self.data[self.position.min(self.data.len())..].iter().position(|byte| *byte == 0).ok_or(ClassIdError::Truncated)?;
+        let bytes = self.read_bytes(len + 1)?;
+
+        Ok(std::str::from_utf8(&bytes[..len]).unwrap_or_default())
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.position = self.position.div_ceil(alignment) * alignment;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_class_ids, ClassIdError};
+
+    /// Write a SerializedFile of version 22 with a GameObject, a typetree-less MonoBehaviour and a Texture2D.
+    fn serialized_file(enable_type_tree: bool) -> Vec<u8> {
+        let mut metadata = Vec::new();
+        metadata.extend(b"2020.3.18f1\0");
+        metadata.extend(38i32.to_le_bytes());
+        metadata.push(enable_type_tree as u8);
+        metadata.extend(3i32.to_le_bytes());
+
+        for class_id in [1i32, 114, 28] {
+            metadata.extend(class_id.to_le_bytes());
+            metadata.push(0);
+            metadata.extend((-1i16).to_le_bytes());
+
+            if class_id == 114 {
+                metadata.extend([0xAA; 16]);
+            }
+
+            metadata.extend([0xBB; 16]);
+
+            if enable_type_tree {
+                // Two nodes and their names
+                metadata.extend(2i32.to_le_bytes());
+                metadata.extend(5i32.to_le_bytes());
+                metadata.extend([0; 64]);
+                metadata.extend(b"Base\0");
+                metadata.extend(0i32.to_le_bytes());
+            }
+        }
+
+        metadata.extend(3i32.to_le_bytes());
+
+        for (path_id, type_id) in [(1i64, 0i32), (-7, 1), (4242, 2)] {
+            // Objects are aligned from the start of the file, and the header is 48 bytes
+            while (48 + metadata.len()) % 4 != 0 {
+                metadata.push(0);
+            }
+
+            metadata.extend(path_id.to_le_bytes());
+            metadata.extend(0i64.to_le_bytes());
+            metadata.extend(16u32.to_le_bytes());
+            metadata.extend(type_id.to_le_bytes());
+        }
+
+        let mut file = Vec::new();
+        file.extend([0; 8]);
+        file.extend(22u32.to_be_bytes());
+        file.extend([0; 4]);
+        // Little endian
+        file.extend([0; 4]);
+        file.extend((metadata.len() as u32).to_be_bytes());
+        file.extend((48 + metadata.len() as i64 + 16).to_be_bytes());
+        file.extend(4096i64.to_be_bytes());
+        file.extend(0i64.to_be_bytes());
+        file.extend(metadata);
+        // Some object data
+        file.extend([0x42; 16]);
+        file
+    }
+
+    /// Wrap the files in a UnityFS bundle, with the blocks info at the end and LZ4 blocks of `block_size` bytes.
+    fn bundle(files: &[(&str, Vec<u8>)], block_size: usize) -> Vec<u8> {
+        let data: Vec<u8> = files.iter().flat_map(|(_, file)| file.clone()).collect();
+
+        let blocks: Vec<(Vec<u8>, usize)> = data.chunks(block_size).map(|chunk| (lz4_flex::block::compress(chunk), chunk.len())).collect();
+
+        let mut info = vec![0; 16];
+        info.extend((blocks.len() as i32).to_be_bytes());
+
+        for (compressed, uncompressed_size) in blocks.iter() {
+            info.extend((*uncompressed_size as u32).to_be_bytes());
+            info.extend((compressed.len() as u32).to_be_bytes());
+            info.extend(2u16.to_be_bytes());
+        }
+
+        info.extend((files.len() as i32).to_be_bytes());
+        let mut offset = 0i64;
+
+        for (name, file) in files {
+            info.extend(offset.to_be_bytes());
+            info.extend((file.len() as i64).to_be_bytes());
+            info.extend(4u32.to_be_bytes());
+            info.extend(name.as_bytes());
+            info.push(0);
+            offset += file.len() as i64;
+        }
+
+        let compressed_info = lz4_flex::block::compress(&info);
+
+        let mut bundle = Vec::new();
+        bundle.extend(b"UnityFS\0");
+        bundle.extend(7u32.to_be_bytes());
+        bundle.extend(b"5.x.x\0");
+        bundle.extend(b"2020.3.18f1\0");
+        bundle.extend(0i64.to_be_bytes());
+        bundle.extend((compressed_info.len() as u32).to_be_bytes());
+        bundle.extend((info.len() as u32).to_be_bytes());
+        bundle.extend((0x80u32 | 0x200 | 2).to_be_bytes());
+
+        while bundle.len() % 16 != 0 {
+            bundle.push(0);
+        }
+
+        blocks.iter().for_each(|(compressed, _)| bundle.extend(compressed));
+        bundle.extend(compressed_info);
+        bundle
+    }
+
+    #[test]
+    fn class_ids_are_read_by_path_id() {
+        for enable_type_tree in [true, false] {
+            let bundle = bundle(&[("CAB-0123", serialized_file(enable_type_tree))], 64);
+            let class_ids = read_class_ids(&bundle, "cab-0123").unwrap();
+
+            assert_eq!(class_ids.len(), 3);
+            assert_eq!(class_ids[&1], 1);
+            assert_eq!(class_ids[&-7], 114);
+            assert_eq!(class_ids[&4242], 28);
+        }
+    }
+
+    #[test]
+    fn files_are_found_by_name() {
+        let bundle = bundle(&[("CAB-0123.resS", vec![0; 300]), ("CAB-0123", serialized_file(true))], 128);
+
+        assert_eq!(read_class_ids(&bundle, "CAB-0123").unwrap()[&4242], 28);
+        assert_eq!(read_class_ids(&bundle, "CAB-4567"), Err(ClassIdError::MissingFile("CAB-4567".to_owned())));
+    }
+
+    #[test]
+    fn unsupported_bundles_are_errors() {
+        assert_eq!(read_class_ids(b"UnityWeb\0", "CAB-0123"), Err(ClassIdError::NotUnityFs));
+
+        let mut lzma = bundle(&[("CAB-0123", serialized_file(true))], 64);
+        // Flags of the header, right before the padding
+        lzma[49] = 0x81;
+        assert_eq!(read_class_ids(&lzma, "CAB-0123"), Err(ClassIdError::UnsupportedCompression(1)));
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, fmt};
+
+/// What identifies a modded bundle to Unity and to the Catalog.
+pub struct BundleIdentity<'a> {
+    /// Name of the directory or zip of the mod providing the bundle.
+    pub mod_name: &'a str,
+    pub path: &'a str,
+    pub cab_name: &'a str,
+    /// Replacement bundles are expected to share the CAB name of the vanilla file they take the place of.
+    pub replaces_vanilla: bool,
+    pub containers: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollisionError {
+    /// A vanilla bundle has the same CAB name, so Unity refuses to load one of them.
+    VanillaCab { cab_name: String, internal_id: String },
+    /// A modded bundle that comes first has the same CAB name.
+    ModdedCab { cab_name: String, mod_name: String, path: String },
+    /// A modded bundle that comes first provides an asset at the same container path.
+    Container { container: String, mod_name: String, path: String },
+}
+
+impl fmt::Display for CollisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionError::VanillaCab { cab_name, internal_id } => {
+                write!(f, "CAB name `{}` is already used by the original bundle `{}`, rebuild the bundle with a different name", cab_name, internal_id)
+            },
+            CollisionError::ModdedCab { cab_name, mod_name, path } => write!(
+                f,
+                "CAB name `{}` is already used by `{}` from mod `{}`, only one of these mods can be installed unless one is rebuilt with a different name",
+                cab_name, path, mod_name
+            ),
+            CollisionError::Container { container, mod_name, path } => write!(
+                f,
+                "container path `{}` is already provided by `{}` from mod `{}`, only one of these mods can be installed",
+                container, path, mod_name
+            ),
+        }
+    }
+}
+
+/// Find the bundles that can't be added alongside the others, with the reason for each of them.
+///
+/// Bundles are expected in path order and the first one to claim a CAB name or container path keeps it, so the outcome is the same on every boot.
+/// `vanilla` maps the CAB names of the game's bundles to their InternalId, as in cache.lut.
+pub fn find_collisions(bundles: &[BundleIdentity], vanilla: &HashMap<String, String>) -> Vec<(usize, CollisionError)> {
+    let mut cab_owners: HashMap<&str, usize> = HashMap::new();
+    let mut container_owners: HashMap<&str, usize> = HashMap::new();
+
+    let mut collisions = Vec::new();
+
+    for (idx, bundle) in bundles.iter().enumerate() {
+        let collision = match (vanilla.get(bundle.cab_name), cab_owners.get(bundle.cab_name)) {
+            (Some(internal_id), _) if !bundle.replaces_vanilla => Some(CollisionError::VanillaCab {
+                cab_name: bundle.cab_name.to_owned(),
+                internal_id: internal_id.to_owned(),
+            }),
+            (_, Some(owner)) => Some(CollisionError::ModdedCab {
+                cab_name: bundle.cab_name.to_owned(),
+                mod_name: bundles[*owner].mod_name.to_owned(),
+                path: bundles[*owner].path.to_owned(),
+            }),
+            _ => bundle.containers.iter().find_map(|container| {
+                container_owners.get(container).map(|owner| CollisionError::Container {
+                    container: (*container).to_owned(),
+                    mod_name: bundles[*owner].mod_name.to_owned(),
+                    path: bundles[*owner].path.to_owned(),
+                })
+            }),
+        };
+
+        match collision {
+            // The bundle is left out entirely, so it doesn't claim anything
+            Some(collision) => collisions.push((idx, collision)),
+            None => {
+                cab_owners.insert(bundle.cab_name, idx);
+                container_owners.extend(bundle.containers.iter().map(|container| (*container, idx)));
+            },
+        }
+    }
+
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{find_collisions, BundleIdentity, CollisionError};
+
+    const VANILLA_CAB: &str = "cab-0123456789abcdef0123456789abcdef";
+    const MODDED_CAB: &str = "cab-fedcba9876543210fedcba9876543210";
+
+    fn bundle<'a>(mod_name: &'a str, path: &'a str, cab_name: &'a str, containers: &[&'a str]) -> BundleIdentity<'a> {
+        BundleIdentity {
+            mod_name,
+            path,
+            cab_name,
+            replaces_vanilla: false,
+            containers: containers.to_vec(),
+        }
+    }
+
+    fn vanilla() -> HashMap<String, String> {
+        HashMap::from([(VANILLA_CAB.to_owned(), "{Runtime}/Switch/shaders.bundle".to_owned())])
+    }
+
+    #[test]
+    fn distinct_bundles_do_not_collide() {
+        let bundles = [
+            bundle("ModA", "a/unit.bundle", MODDED_CAB, &["Assets/unit/a.prefab"]),
+            bundle("ModB", "b/unit.bundle", "cab-00000000000000000000000000000000", &["Assets/unit/b.prefab"]),
+        ];
+
+        assert!(find_collisions(&bundles, &vanilla()).is_empty());
+    }
+
+    #[test]
+    fn cab_names_collide_with_vanilla_unless_replaced() {
+        let mut replacement = bundle("ModB", "shaders.bundle", VANILLA_CAB, &[]);
+        replacement.replaces_vanilla = true;
+
+        let bundles = [bundle("ModA", "a/unit.bundle", VANILLA_CAB, &[]), replacement];
+
+        assert_eq!(
+            find_collisions(&bundles, &vanilla()),
+            vec![(
+                0,
+                CollisionError::VanillaCab {
+                    cab_name: VANILLA_CAB.to_owned(),
+                    internal_id: "{Runtime}/Switch/shaders.bundle".to_owned()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn first_bundle_keeps_what_it_claims() {
+        let bundles = [
+            bundle("ModA", "a/unit.bundle", MODDED_CAB, &["Assets/unit/a.prefab"]),
+            bundle("ModB", "b/unit.bundle", MODDED_CAB, &["Assets/unit/b.prefab"]),
+            bundle(
+                "ModC",
+                "c/unit.bundle",
+                "cab-00000000000000000000000000000000",
+                &["Assets/unit/c.prefab", "Assets/unit/a.prefab"],
+            ),
+            // ModB was left out, so its container path is still free
+            bundle("ModD", "d/unit.bundle", "cab-11111111111111111111111111111111", &["Assets/unit/b.prefab"]),
+        ];
+
+        assert_eq!(
+            find_collisions(&bundles, &vanilla()),
+            vec![
+                (
+                    1,
+                    CollisionError::ModdedCab {
+                        cab_name: MODDED_CAB.to_owned(),
+                        mod_name: "ModA".to_owned(),
+                        path: "a/unit.bundle".to_owned()
+                    }
+                ),
+                (
+                    2,
+                    CollisionError::Container {
+                        container: "Assets/unit/a.prefab".to_owned(),
+                        mod_name: "ModA".to_owned(),
+                        path: "a/unit.bundle".to_owned()
+                    }
+                ),
+            ]
+        );
+    }
+}
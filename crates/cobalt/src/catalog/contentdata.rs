@@ -0,0 +1,421 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+
+/// Type of an ASCII string in the key data of the Catalog.
+const ASCII_STRING: u8 = 0;
+/// Type of an UTF-16 string in the key data of the Catalog.
+const UNICODE_STRING: u8 = 1;
+
+/// How many fields every entry of the Catalog has, as in `ContentCatalogData.k_EntryDataItemPerEntry`.
+const ENTRY_FIELDS: usize = 7;
+const ENTRY_INTERNAL_ID: usize = 0;
+const ENTRY_DEPENDENCY_KEY: usize = 2;
+const ENTRY_DEPENDENCY_HASH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentDataError {
+    /// A field of the Catalog is missing or isn't what Unity writes.
+    Malformed(&'static str),
+    /// No asset of the Catalog has this InternalId.
+    MissingInternalId(String),
+    /// No bundle of the Catalog has this InternalId, so nothing can depend on it.
+    MissingBundle(String),
+}
+
+impl fmt::Display for ContentDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentDataError::Malformed(field) => write!(f, "`{}` of the catalog is malformed", field),
+            ContentDataError::MissingInternalId(internal_id) => write!(f, "InternalId `{}` appears to be missing", internal_id),
+            ContentDataError::MissingBundle(internal_id) => write!(f, "dependency `{}` is not a bundle of the catalog", internal_id),
+        }
+    }
+}
+
+struct Bucket {
+    data_offset: i32,
+    entries: Vec<i32>,
+}
+
+/// The key, bucket and entry tables of a serialized Catalog, as Unity reads them in `ContentCatalogData.CreateLocator`.
+///
+/// addressables-rs can't edit the dependencies of an existing location, so replaced assets are updated here once the Catalog is serialized.
+pub struct ContentCatalogData {
+    /// Every InternalId, with its prefix expanded.
+    internal_ids: Vec<String>,
+    key_data: Vec<u8>,
+    buckets: Vec<Bucket>,
+    entries: Vec<[i32; ENTRY_FIELDS]>,
+    /// Index of the entry of every bundle, by InternalId. Bundles are the locations without dependencies.
+    bundles: HashMap<String, i32>,
+    /// Every key that is a string, since the locator refuses duplicates.
+    string_keys: HashSet<String>,
+    /// Keys added for dependency lists, so assets needing the same bundles share them.
+    dependency_keys: HashMap<Vec<String>, i32>,
+    modified: bool,
+}
+
+impl ContentCatalogData {
+    pub fn from_json(json: &Value) -> Result<Self, ContentDataError> {
+        let prefixes: Vec<&str> = match json.get("m_InternalIdPrefixes") {
+            Some(prefixes) => prefixes
+                .as_array()
+                .ok_or(ContentDataError::Malformed("m_InternalIdPrefixes"))?
+                .iter()
+                .map(|prefix| prefix.as_str().ok_or(ContentDataError::Malformed("m_InternalIdPrefixes")))
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        let internal_ids: Vec<String> = json
+            .get("m_InternalIds")
+            .and_then(Value::as_array)
+            .ok_or(ContentDataError::Malformed("m_InternalIds"))?
+            .iter()
+            .map(|id| id.as_str().map(|id| expand_internal_id(&prefixes, id)).ok_or(ContentDataError::Malformed("m_InternalIds")))
+            .collect::<Result<_, _>>()?;
+
+        let key_data = decode_field(json, "m_KeyDataString")?;
+
+        let bucket_data = decode_field(json, "m_BucketDataString")?;
+        let mut reader = Reader { data: &bucket_data, position: 0, field: "m_BucketDataString" };
+        let bucket_count = reader.read_count()?;
+        let mut buckets = Vec::with_capacity(bucket_count);
+
+        for _ in 0..bucket_count {
+            let data_offset = reader.read_i32()?;
+            let entry_count = reader.read_count()?;
+            let entries = (0..entry_count).map(|_| reader.read_i32()).collect::<Result<_, _>>()?;
+
+            buckets.push(Bucket { data_offset, entries });
+        }
+
+        let entry_data = decode_field(json, "m_EntryDataString")?;
+        let mut reader = Reader { data: &entry_data, position: 0, field: "m_EntryDataString" };
+        let entry_count = reader.read_count()?;
+        let mut entries = Vec::with_capacity(entry_count);
+
+        for _ in 0..entry_count {
+            let mut entry = [0; ENTRY_FIELDS];
+
+            for field in entry.iter_mut() {
+                *field = reader.read_i32()?;
+            }
+
+            if internal_ids.get(entry[ENTRY_INTERNAL_ID] as usize).is_none() || entry[ENTRY_DEPENDENCY_KEY] >= buckets.len() as i32 {
+                return Err(ContentDataError::Malformed("m_EntryDataString"));
+            }
+
+            entries.push(entry);
+        }
+
+        let bundles = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry[ENTRY_DEPENDENCY_KEY] < 0)
+            .map(|(idx, entry)| (internal_ids[entry[ENTRY_INTERNAL_ID] as usize].to_owned(), idx as i32))
+            .collect();
+
+        let string_keys = buckets.iter().filter_map(|bucket| read_string_key(&key_data, bucket.data_offset)).collect();
+
+        Ok(Self {
+            internal_ids,
+            key_data,
+            buckets,
+            entries,
+            bundles,
+            string_keys,
+            dependency_keys: HashMap::new(),
+            modified: false,
+        })
+    }
+
+    /// Get the InternalIds of the bundles an asset depends on.
+    pub fn get_dependencies(&self, internal_id: &str) -> Result<Vec<String>, ContentDataError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| self.internal_ids[entry[ENTRY_INTERNAL_ID] as usize] == internal_id)
+            .ok_or_else(|| ContentDataError::MissingInternalId(internal_id.to_owned()))?;
+
+        let Ok(key) = usize::try_from(entry[ENTRY_DEPENDENCY_KEY]) else {
+            return Ok(Vec::new());
+        };
+
+        self.buckets[key]
+            .entries
+            .iter()
+            .map(|idx| {
+                self.entries
+                    .get(*idx as usize)
+                    .map(|dependency| self.internal_ids[dependency[ENTRY_INTERNAL_ID] as usize].to_owned())
+                    .ok_or(ContentDataError::Malformed("m_BucketDataString"))
+            })
+            .collect()
+    }
+
+    /// Make every location of an asset depend on these bundles, in order.
+    ///
+    /// The key the asset used is left alone, since other assets of the vanilla bundle can still depend on it.
+    pub fn set_dependencies(&mut self, internal_id: &str, dependencies: &[String]) -> Result<(), ContentDataError> {
+        let locations: Vec<usize> = (0..self.entries.len())
+            .filter(|idx| self.internal_ids[self.entries[*idx][ENTRY_INTERNAL_ID] as usize] == internal_id)
+            .collect();
+
+        if locations.is_empty() {
+            return Err(ContentDataError::MissingInternalId(internal_id.to_owned()));
+        }
+
+        let key = match self.dependency_keys.get(dependencies) {
+            Some(key) => *key,
+            None => {
+                let entries = dependencies
+                    .iter()
+                    .map(|dependency| self.bundles.get(dependency).copied().ok_or_else(|| ContentDataError::MissingBundle(dependency.to_owned())))
+                    .collect::<Result<Vec<i32>, _>>()?;
+
+                let key = self.add_key(&format!("{}#dependencies", internal_id), entries);
+                self.dependency_keys.insert(dependencies.to_vec(), key);
+                key
+            },
+        };
+
+        let hash = dependency_hash(dependencies);
+
+        for idx in locations {
+            self.entries[idx][ENTRY_DEPENDENCY_KEY] = key;
+            self.entries[idx][ENTRY_DEPENDENCY_HASH] = hash;
+        }
+
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Write the tables back to the serialized Catalog, if anything changed.
+    pub fn write_to(&self, json: &mut Value) {
+        if !self.modified {
+            return;
+        }
+
+        let mut bucket_data = Vec::with_capacity(4 + self.buckets.iter().map(|bucket| 8 + bucket.entries.len() * 4).sum::<usize>());
+        bucket_data.extend((self.buckets.len() as i32).to_le_bytes());
+
+        for bucket in self.buckets.iter() {
+            bucket_data.extend(bucket.data_offset.to_le_bytes());
+            bucket_data.extend((bucket.entries.len() as i32).to_le_bytes());
+            bucket.entries.iter().for_each(|entry| bucket_data.extend(entry.to_le_bytes()));
+        }
+
+        let mut entry_data = Vec::with_capacity(4 + self.entries.len() * ENTRY_FIELDS * 4);
+        entry_data.extend((self.entries.len() as i32).to_le_bytes());
+        self.entries.iter().flatten().for_each(|field| entry_data.extend(field.to_le_bytes()));
+
+        json["m_KeyDataString"] = Value::String(STANDARD.encode(&self.key_data));
+        json["m_BucketDataString"] = Value::String(STANDARD.encode(bucket_data));
+        json["m_EntryDataString"] = Value::String(STANDARD.encode(entry_data));
+    }
+
+    /// Add a string key and the bucket of the entries it locates, making the name unique if it has to.
+    fn add_key(&mut self, name: &str, entries: Vec<i32>) -> i32 {
+        let mut key = name.to_owned();
+        let mut suffix = 1;
+
+        while self.string_keys.contains(&key) {
+            suffix += 1;
+            key = format!("{}{}", name, suffix);
+        }
+
+        let data_offset = self.key_data.len() as i32;
+
+        if key.is_ascii() {
+            self.key_data.push(ASCII_STRING);
+            self.key_data.extend((key.len() as i32).to_le_bytes());
+            self.key_data.extend(key.as_bytes());
+        } else {
+            let encoded: Vec<u8> = key.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            self.key_data.push(UNICODE_STRING);
+            self.key_data.extend((encoded.len() as i32).to_le_bytes());
+            self.key_data.extend(encoded);
+        }
+
+        // The key data starts with how many keys there are
+        let key_count = self.buckets.len() as i32 + 1;
+        self.key_data[..4].copy_from_slice(&key_count.to_le_bytes());
+
+        self.buckets.push(Bucket { data_offset, entries });
+        self.string_keys.insert(key);
+
+        self.buckets.len() as i32 - 1
+    }
+}
+
+/// Add the prefix an InternalId refers to, as in `ContentCatalogData.ExpandInternalId`.
+fn expand_internal_id(prefixes: &[&str], internal_id: &str) -> String {
+    let Some((idx, rest)) = internal_id.rsplit_once('#') else {
+        return internal_id.to_owned();
+    };
+
+    match idx.parse::<usize>().ok().and_then(|idx| prefixes.get(idx)) {
+        Some(prefix) => format!("{}{}", prefix, rest),
+        None => internal_id.to_owned(),
+    }
+}
+
+fn decode_field(json: &Value, field: &'static str) -> Result<Vec<u8>, ContentDataError> {
+    json.get(field)
+        .and_then(Value::as_str)
+        .and_then(|data| STANDARD.decode(data).ok())
+        .filter(|data| data.len() >= 4)
+        .ok_or(ContentDataError::Malformed(field))
+}
+
+fn read_string_key(key_data: &[u8], data_offset: i32) -> Option<String> {
+    let offset = usize::try_from(data_offset).ok()?;
+    let kind = *key_data.get(offset)?;
+    let len = i32::from_le_bytes(key_data.get(offset + 1..offset + 5)?.try_into().ok()?);
+    let bytes = key_data.get(offset + 5..offset + 5 + usize::try_from(len).ok()?)?;
+
+    match kind {
+        ASCII_STRING => Some(String::from_utf8_lossy(bytes).into_owned()),
+        UNICODE_STRING => {
+            let units: Vec<u16> = bytes.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+            Some(String::from_utf16_lossy(&units))
+        },
+        _ => None,
+    }
+}
+
+/// Unity caches the dependencies of a location by this hash, so locations with different dependencies must not share it.
+fn dependency_hash(dependencies: &[String]) -> i32 {
+    // FNV-1a
+    let hash = dependencies.iter().flat_map(|dependency| dependency.bytes().chain([0])).fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+
+    hash as i32
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    field: &'static str,
+}
+
+impl Reader<'_> {
+    fn read_i32(&mut self) -> Result<i32, ContentDataError> {
+        let bytes = self.data.get(self.position..self.position + 4).ok_or(ContentDataError::Malformed(self.field))?;
+        self.position += 4;
+
+        Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_count(&mut self) -> Result<usize, ContentDataError> {
+        usize::try_from(self.read_i32()?).map_err(|_| ContentDataError::Malformed(self.field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::{json, Value};
+
+    use super::{ContentCatalogData, ContentDataError};
+
+    fn string_key(key_data: &mut Vec<u8>, key: &str) -> i32 {
+        let offset = key_data.len() as i32;
+        key_data.push(0);
+        key_data.extend((key.len() as i32).to_le_bytes());
+        key_data.extend(key.as_bytes());
+        offset
+    }
+
+    /// Two bundles and two assets of the first one, which both depend on it and on shaders.bundle.
+    fn catalog() -> Value {
+        let mut key_data = 4i32.to_le_bytes().to_vec();
+        let keys = ["unit.bundle", "shaders.bundle", "Assets/unit.prefab", "dependencies"].map(|key| string_key(&mut key_data, key));
+
+        let buckets: [(i32, &[i32]); 4] = [(keys[0], &[0]), (keys[1], &[1]), (keys[2], &[2]), (keys[3], &[0, 1])];
+        let mut bucket_data = 4i32.to_le_bytes().to_vec();
+
+        for (offset, entries) in buckets {
+            bucket_data.extend(offset.to_le_bytes());
+            bucket_data.extend((entries.len() as i32).to_le_bytes());
+            entries.iter().for_each(|entry| bucket_data.extend(entry.to_le_bytes()));
+        }
+
+        let entries: [[i32; 7]; 4] = [[0, 0, -1, 0, -1, 0, 0], [1, 0, -1, 0, -1, 1, 0], [2, 1, 3, 7, -1, 2, 1], [3, 1, 3, 7, -1, 2, 1]];
+        let mut entry_data = 4i32.to_le_bytes().to_vec();
+        entries.iter().flatten().for_each(|field| entry_data.extend(field.to_le_bytes()));
+
+        json!({
+            "m_InternalIdPrefixes": ["{Runtime}/Switch/"],
+            "m_InternalIds": ["0#unit.bundle", "0#shaders.bundle", "Assets/unit.prefab", "Assets/unit.mat"],
+            "m_KeyDataString": STANDARD.encode(key_data),
+            "m_BucketDataString": STANDARD.encode(bucket_data),
+            "m_EntryDataString": STANDARD.encode(entry_data),
+        })
+    }
+
+    #[test]
+    fn dependencies_are_read_from_the_bucket_of_the_dependency_key() {
+        let content = ContentCatalogData::from_json(&catalog()).unwrap();
+
+        assert_eq!(
+            content.get_dependencies("Assets/unit.prefab"),
+            Ok(vec!["{Runtime}/Switch/unit.bundle".to_owned(), "{Runtime}/Switch/shaders.bundle".to_owned()])
+        );
+        assert_eq!(content.get_dependencies("{Runtime}/Switch/unit.bundle"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn new_dependencies_only_apply_to_the_replaced_asset() {
+        let mut json = catalog();
+        let mut content = ContentCatalogData::from_json(&json).unwrap();
+
+        let dependencies = ["{Runtime}/Switch/unit.bundle".to_owned(), "{Runtime}/Switch/shaders.bundle".to_owned(), "{Runtime}/Switch/unit.bundle".to_owned()];
+        content.set_dependencies("Assets/unit.prefab", &dependencies[..2]).unwrap();
+        content.set_dependencies("Assets/unit.mat", &dependencies[1..]).unwrap();
+        content.write_to(&mut json);
+
+        let content = ContentCatalogData::from_json(&json).unwrap();
+
+        assert_eq!(content.get_dependencies("Assets/unit.prefab"), Ok(dependencies[..2].to_vec()));
+        assert_eq!(content.get_dependencies("Assets/unit.mat"), Ok(dependencies[1..].to_vec()));
+        assert_eq!(content.buckets.len(), 6);
+        assert!(content.string_keys.contains("Assets/unit.prefab#dependencies"));
+        assert!(content.string_keys.contains("Assets/unit.mat#dependencies"));
+        assert_eq!(&content.key_data[..4], &6i32.to_le_bytes());
+    }
+
+    #[test]
+    fn dependency_lists_are_shared() {
+        let mut content = ContentCatalogData::from_json(&catalog()).unwrap();
+
+        let dependencies = ["{Runtime}/Switch/shaders.bundle".to_owned()];
+        content.set_dependencies("Assets/unit.prefab", &dependencies).unwrap();
+        content.set_dependencies("Assets/unit.mat", &dependencies).unwrap();
+
+        assert_eq!(content.buckets.len(), 5);
+        assert_eq!(content.entries[2][2], content.entries[3][2]);
+    }
+
+    #[test]
+    fn dependencies_must_be_bundles_of_the_catalog() {
+        let mut content = ContentCatalogData::from_json(&catalog()).unwrap();
+
+        assert_eq!(
+            content.set_dependencies("Assets/unit.prefab", &["{Runtime}/Switch/mymod/textures.bundle".to_owned()]),
+            Err(ContentDataError::MissingBundle("{Runtime}/Switch/mymod/textures.bundle".to_owned()))
+        );
+        assert_eq!(
+            content.set_dependencies("Assets/missing.prefab", &["{Runtime}/Switch/unit.bundle".to_owned()]),
+            Err(ContentDataError::MissingInternalId("Assets/missing.prefab".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, fmt};
+
+/// Prefix of externals pointing to another AssetBundle, as in `archive:/CAB-<hash>/CAB-<hash>`.
+const ARCHIVE_PREFIX: &str = "archive:/";
+
+/// Prefixes of externals pointing to resources built into the game, which are not AssetBundles and never need to be loaded.
+const BUILTIN_PREFIXES: [&str; 3] = ["library:/", "library/", "resources/"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencyError {
+    /// The external doesn't follow any format we know about.
+    Malformed(String),
+    /// No vanilla or modded bundle has this CAB name.
+    MissingBundle(String),
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::Malformed(path) => write!(f, "dependency `{}` is not a path to an AssetBundle or a built-in resource", path),
+            DependencyError::MissingBundle(cab) => write!(
+                f,
+                "dependency `{}` is not part of the original game files or of any modded bundle, make sure every required mod is installed",
+                cab
+            ),
+        }
+    }
+}
+
+/// Turn the externals of a bundle into the InternalIds of the bundles it depends on.
+pub struct DependencyResolver<'a> {
+    /// CAB names of the vanilla bundles to their InternalId, from cache.lut.
+    vanilla: &'a HashMap<String, String>,
+    /// CAB names of the modded bundles to their InternalId.
+    modded: &'a HashMap<String, String>,
+}
+
+impl<'a> DependencyResolver<'a> {
+    pub fn new(vanilla: &'a HashMap<String, String>, modded: &'a HashMap<String, String>) -> Self {
+        Self { vanilla, modded }
+    }
+
+    /// Get the InternalId of the bundle an external points to, or `None` if it is a built-in resource.
+    pub fn resolve(&self, external: &str) -> Result<Option<&'a str>, DependencyError> {
+        let lowercase = external.to_lowercase();
+
+        if BUILTIN_PREFIXES.iter().any(|prefix| lowercase.starts_with(prefix)) {
+            return Ok(None);
+        }
+
+        let cab = lowercase
+            .strip_prefix(ARCHIVE_PREFIX)
+            .and_then(|path| path.split('/').next())
+            .filter(|cab| cab.starts_with("cab-"))
+            .ok_or_else(|| DependencyError::Malformed(external.to_owned()))?;
+
+        self.vanilla
+            .get(cab)
+            .or_else(|| self.modded.get(cab))
+            .map(|internal_id| Some(internal_id.as_str()))
+            .ok_or_else(|| DependencyError::MissingBundle(cab.to_owned()))
+    }
+
+    /// Build the dependency list of a bundle for the Catalog, which starts with the bundle itself.
+    pub fn resolve_all<'b>(&self, internal_id: &str, externals: impl IntoIterator<Item = &'b str>) -> Result<Vec<String>, DependencyError> {
+        let mut dependencies = vec![internal_id.to_owned()];
+
+        for external in externals {
+            if let Some(dependency) = self.resolve(external)? {
+                if !dependencies.iter().any(|existing| existing == dependency) {
+                    dependencies.push(dependency.to_owned());
+                }
+            }
+        }
+
+        Ok(dependencies)
+    }
+}
+
+/// Add the dependencies of a modded bundle to the ones of the vanilla bundle it replaces.
+///
+/// Vanilla dependencies are kept first and in order, since assets the mod didn't touch might still need them.
+pub fn merge_dependencies(vanilla: &[String], modded: &[String]) -> Vec<String> {
+    let mut merged = vanilla.to_vec();
+
+    for dependency in modded {
+        if !merged.contains(dependency) {
+            merged.push(dependency.to_owned());
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{merge_dependencies, DependencyError, DependencyResolver};
+
+    const VANILLA_CAB: &str = "cab-0123456789abcdef0123456789abcdef";
+    const MODDED_CAB: &str = "cab-fedcba9876543210fedcba9876543210";
+
+    fn luts() -> (HashMap<String, String>, HashMap<String, String>) {
+        let vanilla = HashMap::from([(VANILLA_CAB.to_owned(), "{Runtime}/Switch/shaders.bundle".to_owned())]);
+        let modded = HashMap::from([(MODDED_CAB.to_owned(), "{Runtime}/Switch/mymod/textures.bundle".to_owned())]);
+
+        (vanilla, modded)
+    }
+
+    #[test]
+    fn vanilla_and_modded_bundles_are_found() {
+        let (vanilla, modded) = luts();
+        let resolver = DependencyResolver::new(&vanilla, &modded);
+
+        assert_eq!(
+            resolver.resolve("archive:/CAB-0123456789ABCDEF0123456789ABCDEF/CAB-0123456789ABCDEF0123456789ABCDEF"),
+            Ok(Some("{Runtime}/Switch/shaders.bundle"))
+        );
+        assert_eq!(
+            resolver.resolve("archive:/CAB-fedcba9876543210fedcba9876543210/CAB-fedcba9876543210fedcba9876543210.resS"),
+            Ok(Some("{Runtime}/Switch/mymod/textures.bundle"))
+        );
+    }
+
+    #[test]
+    fn builtin_resources_are_not_dependencies() {
+        let (vanilla, modded) = luts();
+        let resolver = DependencyResolver::new(&vanilla, &modded);
+
+        assert_eq!(resolver.resolve("Library/unity default resources"), Ok(None));
+        assert_eq!(resolver.resolve("Resources/unity_builtin_extra"), Ok(None));
+        assert_eq!(resolver.resolve("Library:/unity default resources"), Ok(None));
+    }
+
+    #[test]
+    fn unresolved_dependencies_are_errors() {
+        let (vanilla, modded) = luts();
+        let resolver = DependencyResolver::new(&vanilla, &modded);
+
+        assert_eq!(
+            resolver.resolve("archive:/CAB-00000000000000000000000000000000/CAB-00000000000000000000000000000000"),
+            Err(DependencyError::MissingBundle("cab-00000000000000000000000000000000".to_owned()))
+        );
+        assert_eq!(resolver.resolve("archive:/"), Err(DependencyError::Malformed("archive:/".to_owned())));
+        assert_eq!(resolver.resolve("Assets/Share/thing.mat"), Err(DependencyError::Malformed("Assets/Share/thing.mat".to_owned())));
+    }
+
+    #[test]
+    fn dependency_lists_start_with_the_bundle() {
+        let (vanilla, modded) = luts();
+        let resolver = DependencyResolver::new(&vanilla, &modded);
+
+        let externals = [
+            "Library/unity default resources",
+            "archive:/CAB-0123456789abcdef0123456789abcdef/CAB-0123456789abcdef0123456789abcdef",
+            "archive:/CAB-0123456789abcdef0123456789abcdef/CAB-0123456789abcdef0123456789abcdef.resS",
+        ];
+
+        assert_eq!(
+            resolver.resolve_all("{Runtime}/Switch/mymod/unit.bundle", externals),
+            Ok(vec!["{Runtime}/Switch/mymod/unit.bundle".to_owned(), "{Runtime}/Switch/shaders.bundle".to_owned()])
+        );
+    }
+
+    #[test]
+    fn replacements_keep_vanilla_dependencies() {
+        let vanilla = ["unit.bundle".to_owned(), "shaders.bundle".to_owned()];
+        let modded = ["unit.bundle".to_owned(), "mymod/textures.bundle".to_owned(), "shaders.bundle".to_owned()];
+
+        assert_eq!(
+            merge_dependencies(&vanilla, &modded),
+            vec!["unit.bundle".to_owned(), "shaders.bundle".to_owned(), "mymod/textures.bundle".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Roots of the Unity projects the game was built from, which the game doesn't include when it asks for an asset.
+const DEFAULT_PREFIXES: [&str; 7] = [
+    "Assets/Share/Addressables/",
+    "Assets/Project/Addressables/",
+    "Assets/Share/Scenes/Map/",
+    "Patch/Patch0/",
+    "Patch/Patch1/",
+    "Patch/Patch2/",
+    "Patch/Patch3/",
+];
+
+/// Extensions the game doesn't include when it asks for an asset.
+const DEFAULT_SUFFIXES: [&str; 5] = [".prefab", ".png", ".asset", ".unity", ".fbx"];
+
+/// Turn the container path of an asset into the path the game uses to request it, without the project prefixes and file extension.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct InternalPathRules {
+    prefixes: Vec<String>,
+    suffixes: Vec<String>,
+}
+
+impl Default for InternalPathRules {
+    fn default() -> Self {
+        Self {
+            prefixes: DEFAULT_PREFIXES.iter().map(|prefix| prefix.to_string()).collect(),
+            suffixes: DEFAULT_SUFFIXES.iter().map(|suffix| suffix.to_string()).collect(),
+        }
+    }
+}
+
+impl InternalPathRules {
+    /// Add the rules of a mod or bundle after the ones that are already there.
+    pub fn extend(&mut self, prefixes: &[String], suffixes: &[String]) {
+        for prefix in prefixes {
+            if !self.prefixes.contains(prefix) {
+                self.prefixes.push(prefix.to_owned());
+            }
+        }
+
+        for suffix in suffixes {
+            if !self.suffixes.contains(suffix) {
+                self.suffixes.push(suffix.to_owned());
+            }
+        }
+    }
+
+    pub fn apply<'a>(&self, container_internal_id: &'a str) -> &'a str {
+        let internal_path = self.prefixes.iter().fold(container_internal_id, |path, prefix| {
+            path.trim_start_matches(prefix.as_str())
+        });
+
+        self.suffixes.iter().fold(internal_path, |path, suffix| {
+            path.trim_end_matches(suffix.as_str())
+        })
+    }
+
+    /// Identify the rules, so cached internal paths can be thrown away when they change.
+    pub fn signature(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InternalPathRules;
+
+    #[test]
+    fn project_roots_and_extensions_are_stripped() {
+        let rules = InternalPathRules::default();
+
+        assert_eq!(rules.apply("Assets/Share/Addressables/Unit/Model/uBody/Swd0AM/c000.prefab"), "Unit/Model/uBody/Swd0AM/c000");
+        assert_eq!(rules.apply("Patch/Patch2/UI/Icon/Face.png"), "UI/Icon/Face");
+        assert_eq!(rules.apply("Somewhere/Else.mat"), "Somewhere/Else.mat");
+    }
+
+    #[test]
+    fn mods_can_add_rules() {
+        let mut rules = InternalPathRules::default();
+        let signature = rules.signature();
+
+        rules.extend(&["Assets/MyProject/".to_owned()], &[".mat".to_owned(), ".png".to_owned()]);
+
+        assert_eq!(rules.apply("Assets/MyProject/Somewhere/Else.mat"), "Somewhere/Else");
+        assert_ne!(rules.signature(), signature);
+    }
+}
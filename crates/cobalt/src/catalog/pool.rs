@@ -0,0 +1,132 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Condvar, Mutex,
+};
+
+/// Amount of memory that can be reserved by the workers at once.
+struct MemoryBudget {
+    limit: u64,
+    used: Mutex<u64>,
+    freed: Condvar,
+}
+
+impl MemoryBudget {
+    fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: Mutex::new(0),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Wait until `amount` fits in the budget and reserve it.
+    ///
+    /// Something bigger than the whole budget is let through once nothing else is reserved, so it can't wait forever.
+    fn acquire(&self, amount: u64) {
+        let mut used = self.used.lock().unwrap();
+
+        while *used != 0 && *used + amount > self.limit {
+            used = self.freed.wait(used).unwrap();
+        }
+
+        *used += amount;
+    }
+
+    fn release(&self, amount: u64) {
+        *self.used.lock().unwrap() -= amount;
+        self.freed.notify_all();
+    }
+}
+
+/// Run `job` on every item using `worker_count` threads, and return the results in the order of the items.
+///
+/// `cost` is the amount of memory a job needs, such as the size of the file it loads. Jobs wait for enough of `memory_limit` to be free before starting.
+pub fn run_jobs<T, R>(
+    items: &[T],
+    worker_count: usize,
+    memory_limit: u64,
+    cost: impl Fn(&T) -> u64 + Sync,
+    job: impl Fn(&T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let budget = MemoryBudget::new(memory_limit);
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for idx in 0..worker_count.clamp(1, items.len().max(1)) {
+            let (budget, next, results, cost, job) = (&budget, &next, &results, &cost, &job);
+
+            std::thread::Builder::new()
+                .name(format!("catalog_{}", idx))
+                .stack_size(0x100000)
+                .spawn_scoped(scope, move || {
+                    // Keep grabbing items until none are left
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        let amount = cost(item);
+
+                        budget.acquire(amount);
+                        let result = job(item);
+                        budget.release(amount);
+
+                        results.lock().unwrap()[index] = Some(result);
+                    }
+                })
+                .expect("Could not spawn a catalog worker");
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().map(|result| result.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::run_jobs;
+
+    #[test]
+    fn results_follow_the_order_of_the_items() {
+        let items: Vec<u64> = (0..50).collect();
+
+        let results = run_jobs(&items, 3, 100, |_| 1, |item| item * 2);
+
+        assert_eq!(results, items.iter().map(|item| item * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn memory_limit_is_respected() {
+        let in_use = AtomicU64::new(0);
+        let peak = AtomicU64::new(0);
+
+        let items = [40u64, 30, 50, 20, 60, 10, 70];
+
+        run_jobs(&items, 4, 100, |size| *size, |size| {
+            let current = in_use.fetch_add(*size, Ordering::SeqCst) + size;
+            peak.fetch_max(current, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            in_use.fetch_sub(*size, Ordering::SeqCst);
+        });
+
+        assert!(peak.load(Ordering::SeqCst) <= 100);
+    }
+
+    #[test]
+    fn items_bigger_than_the_limit_still_run() {
+        assert_eq!(run_jobs(&[150u64, 20], 2, 100, |size| *size, |size| *size), vec![150, 20]);
+    }
+
+    #[test]
+    fn nothing_to_do() {
+        assert!(run_jobs(&[] as &[u64], 3, 100, |_| 1, |item| *item).is_empty());
+    }
+}
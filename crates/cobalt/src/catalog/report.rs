@@ -0,0 +1,67 @@
+use std::{fmt, io, sync::RwLock};
+
+use camino::Utf8Path;
+
+/// Where the errors of the last catalog patching are written to.
+pub const REPORT_PATH: &str = "sd:/engage/catalog_errors.txt";
+
+/// Errors that prevented modded bundles from being added to the Catalog during this boot.
+pub static BUNDLE_ERRORS: RwLock<Vec<BundleError>> = RwLock::new(Vec::new());
+
+/// A modded bundle that was left out of the Catalog, and why.
+#[derive(Debug, Clone)]
+pub struct BundleError {
+    /// Name of the directory or zip of the mod providing the bundle.
+    pub mod_name: String,
+    pub path: String,
+    pub reason: String,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.mod_name, self.path, self.reason)
+    }
+}
+
+/// Keep track of a bundle that could not be added, so the game can keep going without it.
+pub fn report_bundle_error(rel_path: &Utf8Path, reason: impl ToString) {
+    let error = BundleError {
+        mod_name: get_mod_name(rel_path),
+        path: rel_path.to_string(),
+        reason: reason.to_string(),
+    };
+
+    println!("Bundle was not added to the catalog: {}", error);
+
+    BUNDLE_ERRORS.write().unwrap().push(error);
+}
+
+/// Name of the directory or zip of the mod providing a file.
+pub fn get_mod_name(rel_path: &Utf8Path) -> String {
+    mods::manager::Manager::get()
+        .get_mod_root(rel_path)
+        .ok()
+        .and_then(|root| root.file_name())
+        .unwrap_or("Unknown mod")
+        .to_owned()
+}
+
+pub fn has_bundle_errors() -> bool {
+    !BUNDLE_ERRORS.read().unwrap().is_empty()
+}
+
+/// Write every error to the SD, or remove the previous report if everything went fine this time.
+pub fn write_report() -> io::Result<()> {
+    let errors = BUNDLE_ERRORS.read().unwrap();
+
+    if errors.is_empty() {
+        return match std::fs::remove_file(REPORT_PATH) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+
+    let report: String = errors.iter().map(|error| format!("{}\n", error)).collect();
+
+    std::fs::write(REPORT_PATH, report)
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+use super::assettype::AssetKind;
+
+/// Optional `<bundle>.yaml` file placed next to a modded bundle, to tell Cobalt how to register its assets.
+///
+/// ```yaml
+/// asset_types:
+///   Assets/Share/Addressables/Unit/Model/uBody/uBody_Swd0AM_c000/Materials/Body.mat: Material
+/// prefixes:
+///   - Assets/MyProject/
+/// suffixes:
+///   - .mat
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BundleSidecar {
+    /// Kind of asset to register, by container path, for assets that are not detected properly.
+    pub asset_types: HashMap<String, AssetKind>,
+    /// Extra prefixes to strip from the container paths of this bundle, on top of the ones of the game and the mod.
+    pub prefixes: Vec<String>,
+    /// Extra extensions to strip from the container paths of this bundle.
+    pub suffixes: Vec<String>,
+    /// Internal path to register the only asset of the bundle with, instead of deriving it from the container path.
+    pub internal_path: Option<String>,
+    /// Internal path to register assets with, by container path, for bundles with several assets.
+    pub internal_paths: HashMap<String, String>,
+}
+
+impl BundleSidecar {
+    pub fn path(bundle_path: &Utf8Path) -> Utf8PathBuf {
+        Utf8PathBuf::from(format!("{}.yaml", bundle_path))
+    }
+
+    /// Read the sidecar of a bundle, or use the default behavior if it doesn't have one.
+    pub fn load(bundle_path: &Utf8Path) -> Self {
+        let path = Self::path(bundle_path);
+
+        match mods::manager::Manager::get().get_file(&path) {
+            Ok(file) => serde_yaml::from_slice(&file).unwrap_or_else(|err| {
+                println!("Sidecar `{}` could not be read and will be ignored: {}", path, err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+}
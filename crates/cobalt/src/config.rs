@@ -15,6 +15,8 @@ pub mod gamemode;
 pub mod plugins;
 pub mod ringpolishrumble;
 pub mod supportoutfit;
+pub mod vibrationcategories;
+pub mod vibrationmix;
 
 use cobalt::*;
 use plugins::*;
@@ -10,7 +10,12 @@ use engage::{
 use unity::prelude::*;
 
 use super::{
-    super::sequences::mainmenu::cobaltmenu::sequences::settings::{lod::LodSetting, render_scale::RenderScaleSetting, render_scale_toggle::ToggleRenderScaleSetting},
+    super::sequences::mainmenu::cobaltmenu::sequences::settings::{
+        lod::LodSetting,
+        patch_dump::{PatchDumpBaseSetting, PatchDumpSetting},
+        render_scale::RenderScaleSetting,
+        render_scale_toggle::ToggleRenderScaleSetting,
+    },
     combatpopup::CombatPopupSettings,
     combatui::CombatUISettings,
     combatvibration::CombatVibrationsSettings,
@@ -18,6 +23,8 @@ use super::{
     open_anime_all_ondispose,
     ringpolishrumble::{get_ring_polish_item_key, RingPolishVibrationSetting},
     supportoutfit::SupportOutfitSetting,
+    vibrationcategories::VibrationCategoriesSubmenu,
+    vibrationmix::VibrationMixSetting,
 };
 
 pub struct CobaltSubmenu;
@@ -50,6 +57,10 @@ impl ConfigBasicMenuItemCommandMethods for CobaltSubmenu {
                 config_menu.add_item(ConfigBasicMenuItem::new_switch::<CombatVibrationsSettings>(localize::mess::get(
                     "combat_rumble_menu_item_name",
                 )));
+                config_menu.add_item(ConfigBasicMenuItem::new_switch::<VibrationMixSetting>(localize::mess::get("vibration_mix_name")));
+                config_menu.add_item(ConfigBasicMenuItem::new_command::<VibrationCategoriesSubmenu>(localize::mess::get(
+                    "vibration_categories_submenu_item_name",
+                )));
                 config_menu.add_item(ConfigBasicMenuItem::new_switch::<CombatPopupSettings>(localize::mess::get(
                     "combat_popup_name",
                 )));
@@ -68,6 +79,8 @@ impl ConfigBasicMenuItemCommandMethods for CobaltSubmenu {
                     "render_scale_name",
                 )));
                 config_menu.add_item(ConfigBasicMenuItem::new_switch::<LodSetting>(localize::mess::get("lod_name")));
+                config_menu.add_item(ConfigBasicMenuItem::new_switch::<PatchDumpSetting>(localize::mess::get("patch_dump_name")));
+                config_menu.add_item(ConfigBasicMenuItem::new_switch::<PatchDumpBaseSetting>(localize::mess::get("patch_dump_base_name")));
 
                 BasicMenuResult::se_cursor()
             } else {
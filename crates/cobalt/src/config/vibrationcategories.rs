@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{LazyLock, RwLock},
+};
+
+use unity::prelude::*;
+
+use engage::{
+    menu::{
+        config::{ConfigBasicMenuItem, ConfigBasicMenuItemCommandMethods, ConfigBasicMenuItemSwitchMethods},
+        BasicMenuResult, ConfigMenu,
+    },
+    pad::Pad,
+    util::get_instance,
+    vibrationmanager::{vibrate, FREQ_HIGH, FREQ_LOW},
+};
+
+use gamedata::gamedata::VibrationType;
+
+use super::open_anime_all_ondispose;
+use crate::sequences::mainmenu::cobaltmenu::util::{read_from_path, write_to_path};
+
+/// Every category that can be configured, with the name used for its configuration files and the keys for its menu items.
+pub const VIBRATION_CATEGORIES: [(VibrationType, &str, &str, &str); 5] = [
+    (VibrationType::Combat, "combat", "vibration_category_combat_name", "vibration_category_combat_intensity_name"),
+    (VibrationType::MapCombat, "map_combat", "vibration_category_map_combat_name", "vibration_category_map_combat_intensity_name"),
+    (
+        VibrationType::MapSkillEffect,
+        "map_skill_effect",
+        "vibration_category_map_skill_effect_name",
+        "vibration_category_map_skill_effect_intensity_name",
+    ),
+    (VibrationType::UI, "ui", "vibration_category_ui_name", "vibration_category_ui_intensity_name"),
+    (
+        VibrationType::EngageAttack,
+        "engage_attack",
+        "vibration_category_engage_attack_name",
+        "vibration_category_engage_attack_intensity_name",
+    ),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct CategorySetting {
+    pub enabled: bool,
+    /// Multiplier applied to the amplitudes of the events in this category.
+    pub intensity: f32,
+}
+
+impl Default for CategorySetting {
+    fn default() -> Self {
+        Self { enabled: true, intensity: 1.0 }
+    }
+}
+
+pub static CATEGORY_SETTINGS: LazyLock<RwLock<HashMap<VibrationType, CategorySetting>>> = LazyLock::new(|| {
+    let settings = VIBRATION_CATEGORIES
+        .iter()
+        .map(|(category, config_name, _, _)| {
+            let setting = CategorySetting {
+                enabled: !Path::new(&disabled_path(config_name)).exists(),
+                intensity: read_from_path(&intensity_path(config_name)).unwrap_or(1.0),
+            };
+
+            (*category, setting)
+        })
+        .collect();
+
+    RwLock::new(settings)
+});
+
+fn disabled_path(config_name: &str) -> String {
+    format!("sd:/engage/config/vibration_{}_disabled", config_name)
+}
+
+fn intensity_path(config_name: &str) -> String {
+    format!("sd:/engage/config/vibration_{}_intensity", config_name)
+}
+
+/// Get what the user picked for a category. Events without a category, or with `Unknown`, are left alone.
+pub fn get_category_setting(category: Option<VibrationType>) -> CategorySetting {
+    category
+        .and_then(|category| CATEGORY_SETTINGS.read().unwrap().get(&category).copied())
+        .unwrap_or_default()
+}
+
+fn preview(setting: CategorySetting) {
+    if setting.enabled {
+        vibrate(0.15, 0.15 * setting.intensity, 0.10 * setting.intensity, 0.0, FREQ_LOW, FREQ_HIGH)
+    }
+}
+
+pub struct VibrationCategoriesSubmenu;
+
+impl ConfigBasicMenuItemCommandMethods for VibrationCategoriesSubmenu {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let pad_instance = get_instance::<Pad>();
+
+        // Check if A is pressed before executing any of this
+        if pad_instance.npad_state.buttons.a() {
+            if !pad_instance.old_buttons.a() {
+                // Close the Cobalt settings temporarily so they don't get drawn in the background
+                this.menu.close_anime_all();
+
+                // Initialize the menu
+                ConfigMenu::create_bind(this.menu);
+
+                let config_menu = this.menu.proc.child.as_mut().unwrap().cast_mut::<ConfigMenu<ConfigBasicMenuItem>>();
+
+                // Register a OnDispose callback to restore the previous menu
+                config_menu
+                    .get_class_mut()
+                    .get_virtual_method_mut("OnDispose")
+                    .map(|method| method.method_ptr = open_anime_all_ondispose as _)
+                    .unwrap();
+
+                // Clear the buttons in the List so we can add our own
+                config_menu.full_menu_item_list.clear();
+
+                add_category_items::<0>(config_menu);
+                add_category_items::<1>(config_menu);
+                add_category_items::<2>(config_menu);
+                add_category_items::<3>(config_menu);
+                add_category_items::<4>(config_menu);
+
+                BasicMenuResult::se_cursor()
+            } else {
+                BasicMenuResult::new()
+            }
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.command_text = localize::mess::get("submenu_item_commandtext").into();
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = localize::mess::get("vibration_categories_submenu_item_helptext").into();
+    }
+}
+
+fn add_category_items<const IDX: usize>(config_menu: &mut ConfigMenu<ConfigBasicMenuItem>) {
+    let (_, _, name_key, intensity_name_key) = VIBRATION_CATEGORIES[IDX];
+
+    config_menu.add_item(ConfigBasicMenuItem::new_switch::<VibrationCategoryToggle<IDX>>(localize::mess::get(name_key)));
+    config_menu.add_item(ConfigBasicMenuItem::new_switch::<VibrationCategoryIntensity<IDX>>(localize::mess::get(
+        intensity_name_key,
+    )));
+}
+
+/// Turns the vibrations of the category at `IDX` in [`VIBRATION_CATEGORIES`] on or off.
+pub struct VibrationCategoryToggle<const IDX: usize>;
+
+impl<const IDX: usize> ConfigBasicMenuItemSwitchMethods for VibrationCategoryToggle<IDX> {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let (category, config_name, _, _) = VIBRATION_CATEGORIES[IDX];
+
+        let mut setting = get_category_setting(Some(category));
+        let result = ConfigBasicMenuItem::change_key_value_b(setting.enabled);
+
+        if setting.enabled != result {
+            if result {
+                std::fs::remove_file(disabled_path(config_name)).expect("Could not delete the Vibration Category configuration file");
+            } else {
+                std::fs::File::create(disabled_path(config_name)).expect("Could not create the Vibration Category configuration file");
+            }
+
+            setting.enabled = result;
+            CATEGORY_SETTINGS.write().unwrap().insert(category, setting);
+            preview(setting);
+
+            Self::set_command_text(this, None);
+            Self::set_help_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        if get_category_setting(Some(VIBRATION_CATEGORIES[IDX].0)).enabled {
+            this.command_text = localize::mess::get("command_text_on").into();
+        } else {
+            this.command_text = localize::mess::get("command_text_off").into();
+        }
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = localize::mess::get("vibration_category_toggle_helptext").into();
+    }
+}
+
+/// Scales the vibrations of the category at `IDX` in [`VIBRATION_CATEGORIES`].
+pub struct VibrationCategoryIntensity<const IDX: usize>;
+
+impl<const IDX: usize> ConfigBasicMenuItemSwitchMethods for VibrationCategoryIntensity<IDX> {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let (category, config_name, _, _) = VIBRATION_CATEGORIES[IDX];
+
+        let mut setting = get_category_setting(Some(category));
+        let result = ConfigBasicMenuItem::change_key_value_f(setting.intensity, 0.1, 2.0, 0.1);
+
+        if setting.intensity != result {
+            write_to_path(&intensity_path(config_name), &format!("{:.1}", result));
+
+            setting.intensity = result;
+            CATEGORY_SETTINGS.write().unwrap().insert(category, setting);
+            preview(setting);
+
+            Self::set_command_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.command_text = format!("{:.1}", get_category_setting(Some(VIBRATION_CATEGORIES[IDX].0)).intensity).into();
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = localize::mess::get("vibration_category_intensity_helptext").into();
+    }
+}
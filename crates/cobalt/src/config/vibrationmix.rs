@@ -0,0 +1,55 @@
+use unity::prelude::*;
+
+use engage::menu::{
+    config::{ConfigBasicMenuItem, ConfigBasicMenuItemSwitchMethods},
+    BasicMenuResult,
+};
+
+use gamedata::gamedata::MixMode;
+
+use crate::{
+    sequences::mainmenu::cobaltmenu::util::write_to_path,
+    vibrationevents::{VIBRATION_MIXER, VIBRATION_MIX_PATH},
+};
+
+pub struct VibrationMixSetting;
+
+impl ConfigBasicMenuItemSwitchMethods for VibrationMixSetting {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let mode = VIBRATION_MIXER.read().unwrap().mode;
+
+        let result = match ConfigBasicMenuItem::change_key_value_b(mode == MixMode::Sum) {
+            true => MixMode::Sum,
+            false => MixMode::Max,
+        };
+
+        if mode != result {
+            write_to_path(VIBRATION_MIX_PATH, &result.to_string());
+            VIBRATION_MIXER.write().unwrap().mode = result;
+
+            Self::set_command_text(this, None);
+            Self::set_help_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.command_text = match VIBRATION_MIXER.read().unwrap().mode {
+            MixMode::Max => localize::mess::get("vibration_mix_max_command_text"),
+            MixMode::Sum => localize::mess::get("vibration_mix_sum_command_text"),
+        }
+        .into();
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = match VIBRATION_MIXER.read().unwrap().mode {
+            MixMode::Max => localize::mess::get("vibration_mix_max_helptext"),
+            MixMode::Sum => localize::mess::get("vibration_mix_sum_helptext"),
+        }
+        .into();
+    }
+}
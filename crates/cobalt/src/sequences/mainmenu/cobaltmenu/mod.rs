@@ -9,6 +9,7 @@ use engage::{
 pub mod sequences;
 
 use sequences::{
+    catalogerrors::*,
     reloadmsbt::*,
     reloadxml::*,
     settings::*,
@@ -84,6 +85,11 @@ impl CobaltMenuSequence {
             menu_item_list.add(menu_item);
         }
 
+        if crate::catalog::report::has_bundle_errors() {
+            let menu_item = BasicMenuItem::new_impl::<CatalogErrorsMenuItem>();
+            menu_item_list.add(menu_item);
+        }
+
         // Create a BasicMenu and fill it with our item list
         let basic_menu = BasicMenu::new(menu_item_list, menu_content);
         let descs = basic_menu.create_default_desc();
@@ -0,0 +1,40 @@
+use engage::{dialog::yesno::{TwoChoiceDialogMethods, YesNoDialog}, menu::{BasicMenuItem, BasicMenuItemAttribute, BasicMenuItemMethods, BasicMenuResult}};
+use unity::prelude::*;
+
+use crate::catalog::report::{BUNDLE_ERRORS, REPORT_PATH};
+
+/// How many errors are listed in the dialog, the rest can be found in the report on the SD.
+const MAX_DISPLAYED_ERRORS: usize = 3;
+
+pub struct CatalogErrorsMenuItem;
+
+impl BasicMenuItemMethods for CatalogErrorsMenuItem {
+    extern "C" fn get_name(_this: &mut BasicMenuItem, _method_info: OptionalMethod) -> &'static Il2CppString {
+        localize::mess::get("catalog_errors_name").into()
+    }
+
+    extern "C" fn a_call(this: &'static mut BasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let errors = BUNDLE_ERRORS.read().unwrap();
+
+        let mut message = format!("{} ({})\n", localize::mess::get("catalog_errors_message"), errors.len());
+
+        errors.iter().take(MAX_DISPLAYED_ERRORS).for_each(|error| {
+            message.push_str(&format!("\n{}", error));
+        });
+
+        message.push_str(&format!("\n\n{}", REPORT_PATH));
+
+        YesNoDialog::bind::<CatalogErrorsDialog>(this.menu, message, localize::mess::get("catalog_errors_close"), localize::mess::get("catalog_errors_close"));
+
+        BasicMenuResult::se_decide()
+    }
+
+    extern "C" fn build_attributes(_this: &mut BasicMenuItem, _method_info: OptionalMethod) -> BasicMenuItemAttribute {
+        BasicMenuItemAttribute::Enable
+    }
+}
+
+/// Both choices simply close the dialog.
+pub struct CatalogErrorsDialog;
+
+impl TwoChoiceDialogMethods for CatalogErrorsDialog {}
@@ -1,3 +1,4 @@
+pub mod catalogerrors;
 pub mod reloadxml;
 pub mod reloadmsbt;
 pub mod settings;
@@ -8,9 +8,12 @@ use unity::prelude::*;
 
 mod opening;
 pub mod plugins;
+use bundle_loading::BundleLoadingSetting;
 use opening::SkipOpeningSetting;
 use plugins::GlobalPluginSubmenu;
+pub mod bundle_loading;
 pub mod lod;
+pub mod patch_dump;
 pub mod render_scale;
 pub mod render_scale_toggle;
 pub mod util;
@@ -97,4 +100,7 @@ pub extern "C" fn globalconfigsequence_createconfigmenu(this: &mut ConfigSequenc
 
     let skip_opening = ConfigBasicMenuItem::new_switch::<SkipOpeningSetting>(localize::mess::get("skip_opening_menu_item_name"));
     config_menu.add_item(skip_opening);
+
+    let bundle_loading = ConfigBasicMenuItem::new_switch::<BundleLoadingSetting>(localize::mess::get("bundle_loading_menu_item_name"));
+    config_menu.add_item(bundle_loading);
 }
@@ -0,0 +1,56 @@
+use std::sync::{LazyLock, RwLock};
+
+use unity::prelude::*;
+
+use engage::menu::{
+    config::{ConfigBasicMenuItem, ConfigBasicMenuItemSwitchMethods},
+    BasicMenuResult,
+};
+
+pub const BUNDLE_FROM_MEMORY_PATH: &str = "sd:/engage/config/bundle_from_memory";
+
+/// Load every modded bundle from memory, even the ones that are not in a zip.
+pub static LOAD_FROM_MEMORY: LazyLock<RwLock<bool>> = LazyLock::new(|| RwLock::new(std::path::Path::new(BUNDLE_FROM_MEMORY_PATH).exists()));
+
+pub struct BundleLoadingSetting;
+
+impl ConfigBasicMenuItemSwitchMethods for BundleLoadingSetting {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let from_memory = *LOAD_FROM_MEMORY.read().unwrap();
+        let result = ConfigBasicMenuItem::change_key_value_b(from_memory);
+
+        if from_memory != result {
+            if result {
+                std::fs::File::create(BUNDLE_FROM_MEMORY_PATH).expect("Could not create the Bundle Loading configuration file");
+            } else {
+                std::fs::remove_file(BUNDLE_FROM_MEMORY_PATH).expect("Could not delete the Bundle Loading configuration file");
+            }
+
+            *LOAD_FROM_MEMORY.write().unwrap() = result;
+
+            Self::set_command_text(this, None);
+            Self::set_help_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        if *LOAD_FROM_MEMORY.read().unwrap() {
+            this.command_text = localize::mess::get("bundle_loading_memory_command_text").into();
+        } else {
+            this.command_text = localize::mess::get("bundle_loading_file_command_text").into();
+        }
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        if *LOAD_FROM_MEMORY.read().unwrap() {
+            this.help_text = localize::mess::get("bundle_loading_memory_helptext").into();
+        } else {
+            this.help_text = localize::mess::get("bundle_loading_file_helptext").into();
+        }
+    }
+}
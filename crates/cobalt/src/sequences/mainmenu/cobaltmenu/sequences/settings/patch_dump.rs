@@ -0,0 +1,93 @@
+use engage::menu::{
+    config::{ConfigBasicMenuItem, ConfigBasicMenuItemSwitchMethods},
+    BasicMenuResult,
+};
+use unity::prelude::*;
+
+use gamedata::dump::{DumpMode, DUMP_BASE, DUMP_BASE_PATH, DUMP_MODE, DUMP_MODE_PATH};
+
+use crate::sequences::mainmenu::cobaltmenu::util::write_to_path;
+
+pub struct PatchDumpSetting;
+
+impl ConfigBasicMenuItemSwitchMethods for PatchDumpSetting {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let mode = *DUMP_MODE.read().unwrap();
+
+        let result = ConfigBasicMenuItem::change_key_value_i(mode as i32, DumpMode::Off as i32, DumpMode::Diff as i32, 1);
+
+        if mode as i32 != result {
+            let result = DumpMode::from_repr(result).unwrap_or_default();
+
+            write_to_path(DUMP_MODE_PATH, &result.to_string());
+            *DUMP_MODE.write().unwrap() = result;
+
+            Self::set_command_text(this, None);
+            Self::set_help_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.command_text = match *DUMP_MODE.read().unwrap() {
+            DumpMode::Off => localize::mess::get("command_text_off"),
+            DumpMode::Final => localize::mess::get("patch_dump_final_command_text"),
+            DumpMode::Steps => localize::mess::get("patch_dump_steps_command_text"),
+            DumpMode::Diff => localize::mess::get("patch_dump_diff_command_text"),
+        }
+        .into();
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = match *DUMP_MODE.read().unwrap() {
+            DumpMode::Off => localize::mess::get("patch_dump_off_helptext"),
+            DumpMode::Final => localize::mess::get("patch_dump_final_helptext"),
+            DumpMode::Steps => localize::mess::get("patch_dump_steps_helptext"),
+            DumpMode::Diff => localize::mess::get("patch_dump_diff_helptext"),
+        }
+        .into();
+    }
+}
+
+pub struct PatchDumpBaseSetting;
+
+impl ConfigBasicMenuItemSwitchMethods for PatchDumpBaseSetting {
+    extern "C" fn custom_call(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) -> BasicMenuResult {
+        let toggle = *DUMP_BASE.read().unwrap();
+        let result = ConfigBasicMenuItem::change_key_value_b(toggle);
+
+        if toggle != result {
+            if result {
+                std::fs::File::create(DUMP_BASE_PATH).expect("Could not create the Patch Dump Base configuration file");
+            } else {
+                std::fs::remove_file(DUMP_BASE_PATH).expect("Could not delete the Patch Dump Base configuration file");
+            }
+
+            *DUMP_BASE.write().unwrap() = result;
+
+            Self::set_command_text(this, None);
+            Self::set_help_text(this, None);
+            this.update_text();
+
+            BasicMenuResult::se_cursor()
+        } else {
+            BasicMenuResult::new()
+        }
+    }
+
+    extern "C" fn set_command_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        if *DUMP_BASE.read().unwrap() {
+            this.command_text = localize::mess::get("command_text_on").into();
+        } else {
+            this.command_text = localize::mess::get("command_text_off").into();
+        }
+    }
+
+    extern "C" fn set_help_text(this: &mut ConfigBasicMenuItem, _method_info: OptionalMethod) {
+        this.help_text = localize::mess::get("patch_dump_base_helptext").into();
+    }
+}
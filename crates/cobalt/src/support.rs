@@ -31,7 +31,7 @@ static CACHE: LazyLock<RwLock<SupportBook>> = LazyLock::new(|| {
     let mut book = gamedata::gamedata::SupportBook::new();
     println!("Before parsing support book");
 
-    gamedata::merge::<SupportBook>(&mut book, "patches/xml/Support.xml");
+    gamedata::merge::<SupportBook>(&mut book, gamedata::SUPPORT_PATCH_PATH);
     println!("After parsing support book");
     RwLock::new(book)
 });
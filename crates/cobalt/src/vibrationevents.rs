@@ -1,28 +1,64 @@
 use astra_formats::AstraBook;
-use easer::functions::*;
-use gamedata::gamedata::{EasingType, VibrationEvent, VibrationEventBook, VibrationEventChain};
+use gamedata::gamedata::{
+    find_sound_event_mapping, SoundEventContext, SoundEventMapping, VibrationEvent, VibrationEventBook, VibrationEventChain, VibrationMixer,
+};
+pub use gamedata::gamedata::{execute_easing, EasingArgs};
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::LazyLock;
 use std::sync::RwLock;
 
+use crate::sequences::mainmenu::cobaltmenu::util::read_from_path;
+
 /// Perhaps these two hashmaps could be combined into one?
 pub static VIBRATION_EVENTS: LazyLock<RwLock<HashMap<String, VibrationEvent>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
 
 /// Perhaps these two hashmaps could be combined into one?
 pub static VIBRATION_EVENT_CHAINS: LazyLock<RwLock<HashMap<String, VibrationEventChain>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
 
+/// Events registered by plugins, kept aside so they survive hot-reloads of the XML.
+pub static REGISTERED_VIBRATION_EVENTS: LazyLock<RwLock<HashMap<String, VibrationEvent>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Kept in the order of the sheet, as the first mapping that applies wins.
+pub static SOUND_EVENT_MAPPINGS: LazyLock<RwLock<Vec<SoundEventMapping>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Kept sorted by scheduled time, so everything that is due sits at the front. Use [`schedule_vibration_event`] to add to it.
 pub static VIBRATION_EVENT_QUEUE: LazyLock<RwLock<VecDeque<QueuedVibrationEvent>>> = LazyLock::new(|| RwLock::new(VecDeque::new()));
 
+pub const VIBRATION_MIX_PATH: &str = "sd:/engage/config/vibration_mix";
+
+/// Every vibration that is currently being felt, combined on each tick.
+pub static VIBRATION_MIXER: LazyLock<RwLock<VibrationMixer>> =
+    LazyLock::new(|| RwLock::new(VibrationMixer::new(read_from_path(VIBRATION_MIX_PATH).unwrap_or_default())));
+
+static NEXT_VOICE: AtomicUsize = AtomicUsize::new(0);
+
 pub static mut USE_VIBRATION_XML_DEVELOPER_MODE: bool = false;
 
 const VIBRATION_XML_PATH: &str = "sd:/engage/VibrationEvent.xml";
 
+/// Where mods can provide additions and tweaks to the bundled vibration events.
+const VIBRATION_PATCH_PATH: &str = gamedata::VIBRATION_EVENT_PATCH_PATH;
+
 #[derive(Clone)]
 pub struct QueuedVibrationEvent {
     pub vibration_event: VibrationEvent,
     pub scheduled_game_time: f32,
+    /// Events queued on the same voice replace each other in the mixer instead of adding up.
+    pub voice: usize,
+}
+
+/// Queue an event behind everything scheduled at the same time or earlier, so chains and animations from different sources interleave by time.
+pub fn schedule_vibration_event(queue: &mut VecDeque<QueuedVibrationEvent>, event: QueuedVibrationEvent) {
+    let index = queue.partition_point(|queued| queued.scheduled_game_time <= event.scheduled_game_time);
+    queue.insert(index, event);
+}
+
+/// Get a voice nobody else is playing on.
+pub fn next_voice() -> usize {
+    NEXT_VOICE.fetch_add(1, Ordering::Relaxed)
 }
 
 pub fn initialize_vibration_data() {
@@ -34,8 +70,8 @@ pub fn initialize_vibration_data() {
             load_vibration_event_data();
         }
     } else {
-        println!("VibrationEvent.xml not detected - using bundled vibration events only.");
-        update_hashmap(&get_built_in_book())
+        println!("VibrationEvent.xml not detected - using bundled and modded vibration events only.");
+        update_hashmap(&get_merged_book())
     }
 }
 
@@ -44,6 +80,15 @@ fn get_built_in_book() -> VibrationEventBook {
     AstraBook::from_string(built_in_xml).unwrap()
 }
 
+/// The bundled vibration events with every `patches/xml/VibrationEvent.xml` provided by mods merged on top.
+/// Events and chains are matched by their name, so mods can add new ones or replace the bundled ones.
+/// A malformed patch is skipped, so it doesn't take the other mods down with it.
+fn get_merged_book() -> VibrationEventBook {
+    let mut book = get_built_in_book();
+    gamedata::try_merge(&mut book, VIBRATION_PATCH_PATH);
+    book
+}
+
 /// This is used for hot-reloading vibration events while the game is running.
 /// I've tried to make it so nothing crashes if the XML is malformed or missing.
 pub fn load_vibration_event_data() {
@@ -54,26 +99,32 @@ pub fn load_vibration_event_data() {
     }
     println!("Hot-reloading vibration event data...");
     let parsing = std::time::Instant::now();
-    let vibration_event_book: Result<VibrationEventBook, _> = AstraBook::load(VIBRATION_XML_PATH);
-    let vibration_event_book = match vibration_event_book {
-        Ok(data) => data,
-        Err(err) => {
-            println!("Failed to load VibrationEvent.xml: {}. Loading from bundled file instead.", err);
-            get_built_in_book()
-        },
-    };
+    let mut vibration_event_book = get_merged_book();
+
+    // The developer file is applied last so whatever is being worked on wins over the mods, even the rows that match the bundled ones
+    match AstraBook::load(VIBRATION_XML_PATH) {
+        Ok(developer_book) => vibration_event_book.replace_rows(developer_book),
+        Err(err) => println!("Failed to load VibrationEvent.xml: {}. Loading from bundled and modded files instead.", err),
+    }
 
     println!("Parsing vibration data took took {}ms", parsing.elapsed().as_millis());
     update_hashmap(&vibration_event_book);
 }
 
 fn update_hashmap(vibration_event_book: &VibrationEventBook) {
+    // Point out everything that would be silently ignored once converted
+    vibration_event_book.validate().iter().for_each(|issue| println!("VibrationEvent.xml: {}", issue));
+
     let convert_to_hashmap = std::time::Instant::now();
     if let Ok(mut map) = VIBRATION_EVENTS.try_write() {
         map.clear();
         vibration_event_book.vibration_events.data.iter().for_each(|entry| {
             map.insert(entry.name.to_owned(), (*entry).clone().into());
         });
+        // Plugins asked for these explicitly, so they win over the XML
+        if let Ok(registered) = REGISTERED_VIBRATION_EVENTS.try_read() {
+            map.extend(registered.iter().map(|(name, event)| (name.to_owned(), event.clone())));
+        }
     }
     if let Ok(mut chain_map) = VIBRATION_EVENT_CHAINS.try_write() {
         chain_map.clear();
@@ -81,8 +132,22 @@ fn update_hashmap(vibration_event_book: &VibrationEventBook) {
             chain_map.insert(entry.name.to_owned(), (*entry).clone().into());
         });
     }
+    if let Ok(mut mappings) = SOUND_EVENT_MAPPINGS.try_write() {
+        *mappings = vibration_event_book
+            .sound_event_mappings
+            .data
+            .iter()
+            .filter_map(|entry| match SoundEventMapping::try_from(entry) {
+                Ok(mapping) => Some(mapping),
+                Err(e) => {
+                    println!("Failed to parse sound event mapping {}: {}", entry.name, e);
+                    None
+                },
+            })
+            .collect();
+    }
     println!(
-        "Putting VibrationEvents and VibrationEventChains into the hashmaps took {}ms",
+        "Putting VibrationEvents, VibrationEventChains and SoundEventMappings into the hashmaps took {}ms",
         convert_to_hashmap.elapsed().as_millis()
     );
 }
@@ -101,48 +166,6 @@ macro_rules! vibe_log {
     }
 }
 
-pub struct EasingArgs {
-    /// the current time (or position) of the tween. This can be seconds or frames, steps, seconds, ms, whatever as long as the unit is the same as is used for the total time.
-    pub current_time: f32,
-    /// the beginning value of the property.
-    pub beginning_value: f32,
-    /// chage is the change between the beginning and destination value of the property.
-    pub change: f32,
-    /// total time of the tween.
-    pub total_time: f32,
-}
-
-/// Execute an easing function with the given arguments.
-pub fn execute_easing(easing_type: Option<EasingType>, args: EasingArgs) -> f32 {
-    use EasingType::*;
-    let EasingArgs {
-        current_time: t,
-        beginning_value: b,
-        change: c,
-        total_time: d,
-    } = args;
-    let Some(easing_type) = easing_type else {
-        return b;
-    };
-    match easing_type {
-        EaseInExpo => Expo::ease_in(t, b, c, d),
-        EaseInQuint => Quint::ease_in(t, b, c, d),
-        EaseInQuad => Quad::ease_in(t, b, c, d),
-        EaseInCubic => Cubic::ease_in(t, b, c, d),
-        EaseOutCubic => Cubic::ease_out(t, b, c, d),
-        EaseOutSine => Sine::ease_out(t, b, c, d),
-        EaseInBounce => Bounce::ease_in(t, b, c, d),
-        // Reverse types start from 0.0 and work their way up to the beginning value.
-        ReverseEaseInExpo => Expo::ease_in(t, 0.0, b, d),
-        ReverseEaseInQuint => Quint::ease_in(t, 0.0, b, d),
-        ReverseEaseInQuad => Quad::ease_in(t, 0.0, b, d),
-        ReverseEaseInCubic => Cubic::ease_in(t, 0.0, b, d),
-        ReverseEaseOutCubic => Cubic::ease_out(t, 0.0, b, d),
-        ReverseEaseOutSine => Sine::ease_out(t, 0.0, b, d),
-        ReverseEaseInBounce => Bounce::ease_in(t, 0.0, b, d),
-    }
-}
-
 pub fn get_event_from<T: Clone>(event_name: &str, rwlock_map: &LazyLock<RwLock<HashMap<String, T>>>) -> Option<T> {
     match rwlock_map.try_read() {
         Ok(map) => {
@@ -167,3 +190,24 @@ pub fn get_vibration_event(event_name: &str) -> Option<VibrationEvent> {
 pub fn get_vibration_event_chain(event_name: &str) -> Option<VibrationEventChain> {
     return get_event_from(event_name, &VIBRATION_EVENT_CHAINS);
 }
+
+/// Make a vibration event available to everything that runs events by name, on top of the ones defined in the XML.
+/// Registering an event with a name that is already in use replaces it.
+pub fn register_vibration_event(vibration_event: VibrationEvent) {
+    REGISTERED_VIBRATION_EVENTS
+        .write()
+        .unwrap()
+        .insert(vibration_event.name.to_owned(), vibration_event.clone());
+    VIBRATION_EVENTS.write().unwrap().insert(vibration_event.name.to_owned(), vibration_event);
+}
+
+/// Get the mapping that decides what a sound event runs, if there is one.
+pub fn get_sound_event_mapping(sound_event: &str, context: &impl SoundEventContext) -> Option<SoundEventMapping> {
+    match SOUND_EVENT_MAPPINGS.try_read() {
+        Ok(mappings) => find_sound_event_mapping(&mappings, sound_event, context).cloned(),
+        Err(_) => {
+            vibe_log!("Failed to read sound event mappings");
+            None
+        },
+    }
+}
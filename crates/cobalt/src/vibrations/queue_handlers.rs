@@ -4,10 +4,11 @@ use gamedata::gamedata::VibrationEvent;
 use unity::prelude::*;
 
 use crate::vibrationevents::{
-    execute_easing, get_vibration_event, get_vibration_event_chain, EasingArgs, QueuedVibrationEvent, VIBRATION_EVENT_QUEUE,
+    get_vibration_event, get_vibration_event_chain, next_voice, schedule_vibration_event, QueuedVibrationEvent, VIBRATION_EVENT_QUEUE,
+    VIBRATION_MIXER,
 };
 use crate::vibrations::hooks::IS_REWINDING;
-use crate::vibrations::util::do_vibrate;
+use crate::vibrations::util::{apply_category_settings, do_vibrate};
 
 //App.FieldBgmManager$$Tick	7102d56090	void App.FieldBgmManager$$Tick(App_FieldBgmManager_o * __this, MethodInfo * method)	168
 /// Process the vibration queue on every tick. There is probably a better place for this, but this was the most convenient.
@@ -17,42 +18,62 @@ pub fn field_bgm_manager_tick(this: *const u8, method_info: OptionalMethod) -> *
     call_original!(this, method_info)
 }
 
-/// Go through the currently queued vibrations and find the last event that is ready to be run.
-/// If there were multiple events between the last time this was run and now, only the last one will be run, the others will all be skipped.
+/// Go through the currently queued vibrations and hand every event that is ready to the mixer, then update the motors.
+/// The queue is sorted by time, so the first event that isn't ready means none of the following ones are either.
+/// Events that became ready at the same time are all felt, as the mixer combines them.
 pub fn process_queue() {
+    let now = unsafe { get_time() };
+
     if let Ok(mut queue) = VIBRATION_EVENT_QUEUE.try_write() {
-        let mut popped_event_count = 0;
+        if let Ok(mut mixer) = VIBRATION_MIXER.try_write() {
+            let mut popped_event_count = 0;
+
+            while let Some(peek) = queue.front() {
+                if peek.scheduled_game_time > now {
+                    vibe_log!(
+                        "{}: Not ready to run yet - requested time is {}, now is {}. Break.",
+                        peek.vibration_event.name,
+                        peek.scheduled_game_time,
+                        now
+                    );
+                    break;
+                }
 
-        let now = unsafe { get_time() };
+                let queued_vibration_event = queue.pop_front().unwrap();
+                popped_event_count += 1;
 
-        let mut to_run: Option<QueuedVibrationEvent> = None;
+                let Some(vibration_event) = apply_category_settings(queued_vibration_event.vibration_event) else {
+                    continue;
+                };
 
-        loop {
-            match queue.front() {
-                Some(peek) => match peek.scheduled_game_time {
-                    scheduled_game_time if scheduled_game_time <= now => {
-                        to_run = queue.pop_front();
-                        popped_event_count += 1;
-                    },
-                    _ => {
-                        vibe_log!(
-                            "{}: Not ready to run yet - requested time is {}, now is {}. Break.",
-                            peek.vibration_event.name,
-                            peek.scheduled_game_time,
-                            now
-                        );
-                        break;
-                    },
-                },
-                None => break,
+                vibe_log!("{}: Mixing queued event", vibration_event.name);
+                mixer.play(queued_vibration_event.voice, vibration_event, queued_vibration_event.scheduled_game_time);
+            }
+
+            if popped_event_count > 0 {
+                vibe_log!("Popped {} events.", popped_event_count);
             }
         }
-        
-        if let Some(queued_vibration_event) = to_run {
-            vibe_log!("Popped {} events.", popped_event_count);
-            vibe_log!("{}: Running queued event", queued_vibration_event.vibration_event.name);
-            run_vibration_event(&queued_vibration_event.vibration_event);
-        };
+    }
+
+    apply_mix(now);
+}
+
+/// Tell the motors about the combined vibrations, if anything changed since the last time.
+fn apply_mix(now: f32) {
+    let Ok(mut mixer) = VIBRATION_MIXER.try_write() else {
+        return;
+    };
+
+    if let Some(mixed) = mixer.next_change(now) {
+        vibrate(
+            mixed.end - now,
+            mixed.amplitude_magnitude,
+            mixed.sample.amp_low,
+            mixed.sample.amp_high,
+            mixed.sample.freq_low,
+            mixed.sample.freq_high,
+        );
     }
 }
 
@@ -81,18 +102,22 @@ pub fn run_vibration_event_by_name(event_name: &str) {
                     // Immediate events as part of a chain.
                     vibe_log!("{}: Running vibration event.", chain_item.name);
                     run_vibration_event(&vibration_event);
-                } else if vibration_event.easing_type.is_some() {
-                    // Events that are part of a chain but have easing.
-                    vibe_log!("{}: Queuing up vibration event with easing", chain_item.name);
-                    queue_eased_vibration(&vibration_event, chain_item.delay);
+                } else if vibration_event.is_animated() {
+                    // Events that are part of a chain but have easing or keyframes.
+                    vibe_log!("{}: Queuing up vibration event with easing or keyframes", chain_item.name);
+                    queue_animated_vibration(&vibration_event, chain_item.delay);
                 } else {
                     // Events that are part of a chain but don't have easing and simply a delay.
                     if let Ok(mut queue) = VIBRATION_EVENT_QUEUE.try_write() {
                         vibe_log!("{}: Queuing up vibration event.", chain_item.name);
-                        queue.push_back(QueuedVibrationEvent {
-                            vibration_event,
-                            scheduled_game_time: now + chain_item.delay,
-                        });
+                        schedule_vibration_event(
+                            &mut queue,
+                            QueuedVibrationEvent {
+                                vibration_event,
+                                scheduled_game_time: now + chain_item.delay,
+                                voice: next_voice(),
+                            },
+                        );
                     }
                 }
             }
@@ -108,65 +133,58 @@ pub fn run_vibration_event_by_name(event_name: &str) {
 }
 
 pub fn run_vibration_event(vibration_event: &VibrationEvent) {
-    if vibration_event.easing_type.is_some() {
-        queue_eased_vibration(vibration_event, 0.0);
+    if vibration_event.is_animated() {
+        queue_animated_vibration(vibration_event, 0.0);
     } else {
-        vibrate(
-            vibration_event.time,
-            vibration_event.amplitude_magnitude,
-            vibration_event.amp_low,
-            vibration_event.amp_high,
-            vibration_event.freq_low,
-            vibration_event.freq_high,
-        );
+        let now = unsafe { get_time() };
+
+        let Some(vibration_event) = apply_category_settings(vibration_event.clone()) else {
+            vibe_log!("{}: Silencing vibration event as its category is turned off.", vibration_event.name);
+            return;
+        };
+
+        if let Ok(mut mixer) = VIBRATION_MIXER.try_write() {
+            mixer.play(next_voice(), vibration_event, now);
+        }
+
+        // Don't wait for the next tick to feel it
+        apply_mix(now);
     }
 }
 
 const STEPS_PER_SECOND: u32 = 30;
 const DURATION_PER_STEP: f32 = 1.0 / STEPS_PER_SECOND as f32;
 
-/// Greedily calculates all the steps needed to ease the vibration event and queues them up.
-/// Perhaps an alternate implementation could store a currently being executed vibration(s) and then
-/// lazily calculate the next vibraiton to run with easing and summing accounted for when it's needed (on each tick)
-pub fn queue_eased_vibration(vibration_event: &VibrationEvent, delay: f32) {
-    let vibration_length = vibration_event.time;
-    let num_steps = vibration_length * STEPS_PER_SECOND as f32;
+/// Greedily calculates all the steps needed to ease the vibration event or follow its keyframes and queues them up.
+/// Every step is queued on the same voice, so the mixer replaces the previous step instead of adding them up.
+pub fn queue_animated_vibration(vibration_event: &VibrationEvent, delay: f32) {
+    let num_steps = (vibration_event.time * STEPS_PER_SECOND as f32) as usize;
+    let voice = next_voice();
     unsafe {
         let now = get_time();
         if let Ok(mut queue) = VIBRATION_EVENT_QUEUE.try_write() {
-            vibe_log!("{:?}: Queuing up vibration event to ease", vibration_event);
-            for i in 0..num_steps as usize {
+            vibe_log!("{:?}: Queuing up vibration event to animate", vibration_event);
+            for i in 0..num_steps {
                 let slice_time = i as f32 * DURATION_PER_STEP;
-                let mut vibration_event = vibration_event.clone();
+                let Some(mut vibration_event) = vibration_event.step(slice_time) else {
+                    break;
+                };
                 vibration_event.time = 10.0; // Overprovision time to ensure there aren't any gaps due to lag and the next event not firing yet.
-                if i == num_steps as usize - 1 {
+                if i == num_steps - 1 {
                     // last step
                     vibration_event.time = DURATION_PER_STEP; // The last step will be set short to ensure it ends at the right time
                 }
 
-                let amp_high_args: EasingArgs = EasingArgs {
-                    current_time: slice_time,
-                    beginning_value: vibration_event.amp_high,
-                    change: -vibration_event.amp_high, // go to zero
-                    total_time: vibration_length,
-                };
-
-                let amp_low_args: EasingArgs = EasingArgs {
-                    current_time: slice_time,
-                    beginning_value: vibration_event.amp_low,
-                    change: -vibration_event.amp_low, // go to zero
-                    total_time: vibration_length,
-                };
-
-                vibration_event.amp_high = execute_easing(vibration_event.easing_type, amp_high_args);
-                vibration_event.amp_low = execute_easing(vibration_event.easing_type, amp_low_args);
-                vibration_event.easing_type = None; // Clear the easing type so we don't try to ease again
                 let scheduled_game_time = slice_time + now + delay;
                 vibe_log!("{:?}: Queuing up vibration event to run at {}", vibration_event, scheduled_game_time);
-                queue.push_back(QueuedVibrationEvent {
-                    scheduled_game_time,
-                    vibration_event,
-                })
+                schedule_vibration_event(
+                    &mut queue,
+                    QueuedVibrationEvent {
+                        scheduled_game_time,
+                        vibration_event,
+                        voice,
+                    },
+                );
             }
         }
     }
@@ -186,4 +204,7 @@ pub fn combat_skip_clear_queue() {
     if let Ok(mut queue) = VIBRATION_EVENT_QUEUE.try_write() {
         queue.clear();
     }
+    if let Ok(mut mixer) = VIBRATION_MIXER.try_write() {
+        mixer.clear();
+    }
 }
@@ -1,7 +1,16 @@
-use engage::{combat::Character, mapmind::MapMind};
+use engage::{
+    combat::{phase_get_is_critical, phase_get_is_player_side_attack, Character},
+    gamedata::unit::Unit,
+    gameuserdata::GameUserData,
+};
+use gamedata::gamedata::SoundEventContext;
 use unity::prelude::*;
 
-use crate::vibrations::{queue_handlers::run_vibration_event_by_name, util::do_vibrate};
+use crate::{
+    vibe_log,
+    vibrationevents::get_sound_event_mapping,
+    vibrations::{queue_handlers::run_vibration_event_by_name, util::do_vibrate},
+};
 
 #[skyline::hook(offset = 0x24f70b0)]
 pub fn app_sound_manager_post_event(
@@ -16,7 +25,7 @@ pub fn app_sound_manager_post_event(
     //     event_name.unwrap_or("[Blank]".into()).to_string()
     // );
     do_vibrate(|| {
-        process_sound_event_name(event_name);
+        process_sound_event_name(event_name, None);
     });
     call_original!(this, event_name, character, is_get_position, method_info)
 }
@@ -26,7 +35,7 @@ pub fn app_sound_manager_post_event_2(
     this: *const u8,
     event_name: Option<&Il2CppString>,
     game_object: *const u8,
-    character: &Character,
+    character: Option<&Character>,
     is_get_position: bool,
     method_info: OptionalMethod,
 ) -> *const u8 {
@@ -35,7 +44,7 @@ pub fn app_sound_manager_post_event_2(
     //     event_name.unwrap_or("[Blank]".into()).to_string()
     // );
     do_vibrate(|| {
-        process_sound_event_name(event_name);
+        process_sound_event_name(event_name, character);
     });
     call_original!(this, event_name, game_object, character, is_get_position, method_info)
 }
@@ -44,45 +53,61 @@ pub fn app_sound_manager_post_event_2(
 ///
 /// The most generic vibration event handler.
 /// These are all the vibrations that I didn't want to bother finding hooks for.
-/// As a side effect, they also always apply whether friend or foe...
-pub fn process_sound_event_name(event_name: Option<&Il2CppString>) {
+/// The SoundEventMappings sheet decides what to run, otherwise the event or chain with the same name is run.
+/// As a side effect, they also always apply whether friend or foe, unless a mapping says otherwise...
+pub fn process_sound_event_name(event_name: Option<&Il2CppString>, character: Option<&Character>) {
     if let Some(event_name) = event_name {
         let event_name = event_name.to_string();
-        let event_name = check_unit_selection(&event_name).unwrap_or(&event_name);
-        run_vibration_event_by_name(event_name);
+
+        match get_sound_event_mapping(&event_name, &PostedSoundEvent { character }) {
+            Some(mapping) => match mapping.event {
+                Some(vibration_event_name) => run_vibration_event_by_name(&vibration_event_name),
+                None => vibe_log!("{}: Silenced by sound event mapping {}.", event_name, mapping.name),
+            },
+            None => run_vibration_event_by_name(&event_name),
+        }
+    }
+}
+
+/// What the game looks like when the sound event was posted, looked up only if a mapping asks for it.
+struct PostedSoundEvent<'a> {
+    /// Only known for sounds played by a combat character.
+    character: Option<&'a Character>,
+}
+
+impl SoundEventContext for PostedSoundEvent<'_> {
+    fn is_engage_owner(&self) -> Option<bool> {
+        current_map_unit().map(|unit| unit.is_engage_owner())
+    }
+
+    fn is_engaging(&self) -> Option<bool> {
+        current_map_unit().map(|unit| unit.is_engaging())
+    }
+
+    fn is_player_side(&self) -> Option<bool> {
+        self.character
+            .map(|character| unsafe { phase_get_is_player_side_attack(character.get_phase(), None) })
+    }
+
+    fn is_critical(&self) -> Option<bool> {
+        self.character
+            .map(|character| unsafe { phase_get_is_critical(character.get_phase(), None) })
     }
 }
 
-/// Special logic for what vibration to play when a unit is selected or released.
-/// Depends on if the unit has an engage partner or not.
-fn check_unit_selection(event_name: &str) -> Option<&str> {
-    match event_name {
-        "UnitTouch" => {
-            let my_unit = MapMind::get_unit();
-            let is_engage_owner = my_unit.is_engage_owner();
-            let is_engaging = my_unit.is_engaging();
-            match (is_engage_owner, is_engaging) {
-                (false, _) => Some("UnitTouch_NotEngageOwner"),
-                (true, false) => {
-                    // do nothing, we're covered by the god appearing
-                    None
-                },
-                (true, true) => Some("UnitTouch_NotEngageOwner"),
-            }
-        },
-        "UnitRelease" => {
-            let my_unit = MapMind::get_unit();
-            let is_engage_owner = my_unit.is_engage_owner();
-            let is_engaging = my_unit.is_engaging();
-            match (is_engage_owner, is_engaging) {
-                (false, _) => Some("UnitRelease_NotEngageOwner"),
-                (true, false) => {
-                    // do nothing, we're covered by the god disappearing
-                    None
-                },
-                (true, true) => Some("UnitRelease_Engaged"),
-            }
-        },
-        _ => None,
+/// Sequences of GameUserData during which MapMind has a unit to look at: the map itself and the sortie preparations.
+const MAP_SEQUENCES: [i32; 2] = [2, 3];
+
+/// The unit MapMind is currently looking at, if a map is running and a unit is selected.
+///
+/// Sound events are also posted in the menus, the Somniel and during cutscenes, where MapMind is not to be trusted.
+fn current_map_unit() -> Option<&'static Unit> {
+    if !MAP_SEQUENCES.contains(&GameUserData::get_sequence()) {
+        return None;
     }
+
+    unsafe { mapmind_get_unit(None) }
 }
+
+#[unity::from_offset("App", "MapMind", "get_Unit")]
+fn mapmind_get_unit(method_info: OptionalMethod) -> Option<&'static Unit>;
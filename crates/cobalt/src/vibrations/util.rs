@@ -2,8 +2,9 @@ use engage::combat::{
     character_get_side, phase_get_is_critical, phase_get_is_player_side_attack, side_is_chain_atk, side_is_master, Character,
 };
 
-use crate::config::combatvibration::COMBAT_VIBRATION_KEY;
+use crate::config::{combatvibration::COMBAT_VIBRATION_KEY, vibrationcategories::get_category_setting};
 use engage::gamevariable::GameVariableManager;
+use gamedata::gamedata::VibrationEvent;
 
 /// Determine if we should vibrate or not, and how strong of a vibration it should be.
 ///
@@ -53,6 +54,23 @@ where
     }
 }
 
+/// Apply the settings the user picked for the category of the event.
+///
+/// Returns nothing if the category is turned off, otherwise the event with its amplitudes scaled by the category's intensity.
+pub fn apply_category_settings(mut event: VibrationEvent) -> Option<VibrationEvent> {
+    let setting = get_category_setting(event.vibration_type);
+
+    if !setting.enabled {
+        return None;
+    }
+
+    event.amplitude_magnitude *= setting.intensity;
+    event.amp_low *= setting.intensity;
+    event.amp_high *= setting.intensity;
+
+    Some(event)
+}
+
 pub enum ConnectionType {
     Normal,
     Critical,
@@ -1,8 +1,4 @@
 mod support;
-mod vibrationevent;
 pub use support::*;
-pub use vibrationevent::*;
-
-pub trait XmlPatch {
-    fn patch(&mut self, patch: Self, original: &Self);
-}
+pub use vibration::*;
+pub use xmlpatch::{patch_group, Keyed, XmlPatch};
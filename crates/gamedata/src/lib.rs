@@ -9,15 +9,55 @@ use unity::prelude::*;
 
 use quick_xml::{events::Event, Reader, Writer};
 
+pub use xmlpatch::{conflict, convert, dump};
 pub mod gamedata;
 use gamedata::*;
+pub mod premerge;
+
+/// Where mods patch the Support book, which Cobalt merges itself.
+pub const SUPPORT_PATCH_PATH: &str = "patches/xml/Support.xml";
+
+/// Where mods patch the vibration events, which Cobalt merges itself.
+pub const VIBRATION_EVENT_PATCH_PATH: &str = "patches/xml/VibrationEvent.xml";
 
 pub fn merge<Book>(book: &mut Book, path: &str)
+where
+    Book: XmlPatch + astra_formats::AstraBook + Clone,
+{
+    merge_with(book, path, |file, _| Some(Book::from_string(String::from_utf8(file).unwrap()).expect(&format!("Could not apply patch"))));
+}
+
+/// Like [`merge`], but a patch that can't be read is reported and skipped instead of stopping the game.
+pub fn try_merge<Book>(book: &mut Book, path: &str)
+where
+    Book: XmlPatch + astra_formats::AstraBook + Clone,
+{
+    merge_with(book, path, |file, source| {
+        let content = match String::from_utf8(file) {
+            Ok(content) => content,
+            Err(_) => {
+                println!("Skipping {} because it is not properly UTF8 encoded", source);
+                return None;
+            },
+        };
+
+        match Book::from_string(content) {
+            Ok(patch) => Some(patch),
+            Err(err) => {
+                println!("Skipping {} because it could not be parsed: {}", source, err);
+                None
+            },
+        }
+    });
+}
+
+/// Apply every patch found at `path`, as read by `read_patch` from the content of the file and its full path.
+fn merge_with<Book>(book: &mut Book, path: &str, read_patch: impl Fn(Vec<u8>, &str) -> Option<Book>)
 where
     Book: XmlPatch + astra_formats::AstraBook + Clone,
 {
     let hashmap = mods::manager::Manager::get();
-    let Ok(files) = hashmap.get_files(path) else {
+    let Ok(files) = hashmap.get_files_with_locations(path) else {
         return;
     };
 
@@ -25,56 +65,197 @@ where
     // extract [a]
     let book_name = path.strip_prefix("patches/xml/").unwrap().strip_suffix(".xml").unwrap();
 
+    let patches: Vec<_> = files
+        .into_iter()
+        .rev()
+        .filter_map(|(file, root)| {
+            let source = root.join(path).to_string();
+            read_patch(file, &source).map(|patch| (source, patch))
+        })
+        .collect();
+
+    if patches.is_empty() {
+        return;
+    }
+
     let original_book = book.clone();
+    let dump_mode = dump::get_mode();
+
+    // Serializing a book is not free, so only do it if something is going to be written
+    let base = (dump_mode != dump::DumpMode::Off || dump::wants_base()).then(|| prettify_xml(&original_book.to_string().unwrap(), book_name));
+
+    if let Some(base) = &base {
+        dump::dump_base(book_name, base);
+    }
+
+    let mut sources = vec![];
 
-    files.into_iter().rev().enumerate().for_each(|(idx, path)| {
-        let patch = Book::from_string(String::from_utf8(path).unwrap()).expect(&format!("Could not apply patch"));
+    for (idx, (source, patch)) in patches.into_iter().enumerate() {
         book.patch(patch, &original_book);
-        let new_book = book.to_string().unwrap();
-        let _ = std::fs::write(&format!("sd:/engage_patches/{}#{idx}.xml", book_name), prettify_xml(&new_book, book_name));
-    });
+        sources.push(source);
+
+        if dump_mode == dump::DumpMode::Steps {
+            dump::dump_step(book_name, idx, &prettify_xml(&book.to_string().unwrap(), book_name));
+        }
+    }
+
+    if let Some(base) = base.filter(|_| dump_mode != dump::DumpMode::Off) {
+        let new_book = prettify_xml(&book.to_string().unwrap(), book_name);
+        dump::dump_final(book_name, &base, &new_book, &sources);
+    }
 }
 
 pub fn string_merge(data: &'static Il2CppArray<u8>, path: &str) -> Option<String>
 {
-    let hashmap = mods::manager::Manager::get();
-    let files = hashmap.get_files(path).ok()?;
-
     // paths will be in format of patches/[a].xml
     // extract [a]
     let book_name = path.strip_prefix("patches/xml/").unwrap().strip_suffix(".xml").unwrap();
 
-    let patching = std::time::Instant::now();
+    if !has_patches(book_name) {
+        return None;
+    }
 
     let base = std::str::from_utf8(&data).expect(&format!("{} XML is not properly UTF8 encoded", book_name));
-    let patches: Vec<_> = files.iter()
-        .map(|patch| std::str::from_utf8(&patch).expect(&format!("{} XML is not properly UTF8 encoded", book_name)))
-        .collect();
+
+    merge_str(base, path)
+}
+
+/// Merge the patches found at `path` on top of the provided base XML.
+///
+/// This does not touch the Il2Cpp runtime, so it is safe to call from worker threads.
+pub fn merge_str(base: &str, path: &str) -> Option<String> {
+    let book_name = path.strip_prefix("patches/xml/").unwrap().strip_suffix(".xml").unwrap();
+
+    let patching = std::time::Instant::now();
+
+    let files = collect_patches(base, book_name);
+
+    if files.is_empty() {
+        return None;
+    }
+
+    let sources: Vec<_> = files.iter().map(|(source, _)| source.to_owned()).collect();
+    let patches: Vec<_> = files.iter().map(|(_, patch)| patch.as_str()).collect();
 
     // avoid invalid leading characters like \ufeff up until <
     let base = base.trim_start_matches(|c| c != '<');
 
     // quickly grab base file
-    #[cfg(debug_assertions)]
-    {
-        println!("save base {book_name}");
-        let path = format!("sd:/engage_patches/base_{}.xml", book_name);
-        // write if it doesn't exist
-        if !std::path::Path::new(&path).exists() {
-            let _ = std::fs::write(&path, base);
-        }
+    dump::dump_base(book_name, base);
+
+    if dump::get_mode() == dump::DumpMode::Steps {
+        // Replay the merge one patch at a time so every intermediate state can be looked at
+        (0..patches.len()).for_each(|idx| dump::dump_step(book_name, idx, &cobalt_xml_merge::merge_all(base, &patches[..=idx])));
     }
 
     let new_book = cobalt_xml_merge::merge_all(base, &patches);
 
     println!("Diffing and patching {book_name} took {}ms", patching.elapsed().as_millis());
 
-    let write_path = format!("sd:/engage_patches/{}.xml", book_name);
-    let _ = std::fs::write(write_path, &new_book);
+    dump::dump_final(book_name, base, &new_book, &sources);
 
     Some(new_book)
 }
 
+/// Check if any mod has a patch for this book, no matter the format.
+pub fn has_patches(book_name: &str) -> bool {
+    let manager = mods::manager::Manager::get();
+
+    convert::PATCH_EXTENSIONS.iter().any(|ext| manager.exists(format!("patches/xml/{}.{}", book_name, ext)))
+}
+
+/// Read every patch provided for a book, converting the CSV and YAML ones to XML.
+///
+/// Patches are ordered by the priority of the mod that provides them, so every format is applied in the same order as XML files would.
+/// Each patch comes with the full path of the file it was read from. Patches that can't be read or converted are reported and skipped.
+fn collect_patches(base: &str, book_name: &str) -> Vec<(String, String)> {
+    let manager = mods::manager::Manager::get();
+
+    let mut patches: Vec<CollectedPatch> = vec![];
+
+    for (ext_idx, ext) in convert::PATCH_EXTENSIONS.iter().enumerate() {
+        let path = format!("patches/xml/{}.{}", book_name, ext);
+
+        for (priority, file, root) in manager.get_files_with_priority(&path).into_iter().flatten() {
+            let Ok(file) = String::from_utf8(file) else {
+                println!("Skipping {} in mod '{}' because it is not properly UTF8 encoded", path, root);
+                continue;
+            };
+
+            let patch = match *ext {
+                "csv" => convert::csv_to_xml(&file, base),
+                "yaml" => convert::yaml_to_xml(&file, base),
+                _ => Ok(file),
+            };
+
+            match patch {
+                Ok(patch) => patches.push(CollectedPatch {
+                    priority,
+                    ext_idx,
+                    converted: *ext != "xml",
+                    mod_root: root.to_string(),
+                    source: root.join(&path).to_string(),
+                    patch,
+                }),
+                Err(err) => println!("Skipping {} in mod '{}' because it could not be converted to XML: {}", path, root, err),
+            }
+        }
+    }
+
+    patches.sort_by_key(|patch| (patch.priority, patch.ext_idx));
+
+    report_conflicts(base, book_name, &patches);
+
+    patches.into_iter().map(|patch| (patch.source, patch.patch)).collect()
+}
+
+struct CollectedPatch {
+    priority: usize,
+    /// Index of the format of the patch in [`convert::PATCH_EXTENSIONS`].
+    ext_idx: usize,
+    /// Whether the patch was converted from CSV or YAML.
+    converted: bool,
+    mod_root: String,
+    source: String,
+    patch: String,
+}
+
+/// Report the rows a CSV or YAML patch changes that another mod changes too, since only the last patch applied to a row is kept.
+fn report_conflicts(base: &str, book_name: &str, patches: &[CollectedPatch]) {
+    // XML patches are full copies of the book, so finding what they change is only worth it when a converted patch can conflict with them
+    if !patches.iter().any(|patch| patch.converted) {
+        return;
+    }
+
+    let base_rows = match conflict::BaseRows::new(base) {
+        Ok(base_rows) => base_rows,
+        Err(err) => {
+            println!("Could not look for conflicting rows in {}.xml: {}", book_name, err);
+            return;
+        },
+    };
+
+    let patch_rows: Vec<_> = patches
+        .iter()
+        .filter_map(|patch| match base_rows.changed_rows(&patch.patch) {
+            Ok(rows) => Some(conflict::PatchRows {
+                source: &patch.source,
+                mod_root: &patch.mod_root,
+                converted: patch.converted,
+                rows,
+            }),
+            Err(err) => {
+                println!("Could not look for conflicting rows in {}: {}", patch.source, err);
+                None
+            },
+        })
+        .collect();
+
+    for conflict in conflict::find_conflicts(&patch_rows) {
+        println!("Conflict in {}.xml: {}", book_name, conflict);
+    }
+}
+
 #[skyline::hook(offset = 0x35faa80)]
 pub fn structdata_import(data: &'static Il2CppArray<u8>, path: &'static Il2CppString, sheet: &'static Il2CppString, method_info: OptionalMethod) {
     // println!("StructData path: {}", path.get_string().unwrap());
@@ -108,6 +289,7 @@ pub fn structdataarray_import(
 #[unity::hook("App", "Database", "Completed")]
 pub fn database_completed_hook(method_info: OptionalMethod) {
     CACHE.lock().unwrap().clear();
+    premerge::clear_premerged();
     call_original!(method_info);
 }
 
@@ -127,8 +309,12 @@ fn common_patch(data: &'static Il2CppArray<u8>, path: &Il2CppString) -> Option<&
         None => {
             // The file hasn't been patched yet
 
+            // Check if a worker already merged it during boot, and merge it ourselves if not.
             // We ignore files that are not supported or the user doesn't have patches for, so let's check if this is a file we patched
-            if let Some(file) = string_merge(data, &format!("patches/xml/{}.xml", path.to_string().as_str())) {
+            let merged = premerge::take_premerged(&path.to_string(), &data)
+                .or_else(|| string_merge(data, &format!("patches/xml/{}.xml", path.to_string().as_str())));
+
+            if let Some(file) = merged {
                 // Insert it into the cache so we don't patch it again if queried later
                 let _ = cache.insert(path.to_string(), file.to_owned());
 
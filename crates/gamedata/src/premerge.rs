@@ -0,0 +1,180 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::Hasher,
+    sync::{mpsc, Arc, LazyLock, Mutex},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::convert::PATCH_EXTENSIONS;
+
+/// How many sheets can be merged at the same time. The game keeps a core for itself, so we don't need more than this.
+const WORKER_COUNT: usize = 3;
+
+/// Patches of the books that live in `patches/xml` but are merged by Cobalt itself with [`crate::merge`] instead of being imported by the game.
+///
+/// Those are left out of the pre-merge, otherwise the workers would merge them at boot for nobody.
+const COBALT_BOOK_PATCHES: [&str; 2] = [crate::SUPPORT_PATCH_PATH, crate::VIBRATION_EVENT_PATCH_PATH];
+
+/// Check if a sheet is one of the books Cobalt merges itself, no matter the case of its name.
+fn is_cobalt_book(sheet: &str) -> bool {
+    COBALT_BOOK_PATCHES
+        .iter()
+        .filter_map(|path| Utf8Path::new(path).file_stem())
+        .any(|book| book.eq_ignore_ascii_case(sheet))
+}
+
+/// A sheet merged by a worker, along with the hash of the base it was merged on top of.
+type PremergedSheet = (u64, String);
+
+/// Sheets that are currently being merged or are done merging, waiting for the import hooks to pick them up.
+static PREMERGED: LazyLock<Mutex<HashMap<String, mpsc::Receiver<Option<PremergedSheet>>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Start merging every sheet that has a patch in `patches/xml` on worker threads.
+///
+/// This does not block. The import hooks will pick the results up through [`take_premerged`] when the game asks for the sheet.
+pub fn premerge_patched_sheets() {
+    let manager = mods::manager::Manager::get();
+
+    let Ok(dir) = manager.get_directory("patches/xml") else {
+        return;
+    };
+
+    let Ok(files) = manager.get_files_in_directory(dir) else {
+        return;
+    };
+
+    let mut premerged = PREMERGED.lock().unwrap();
+
+    let queue: VecDeque<(String, mpsc::Sender<Option<PremergedSheet>>)> = files
+        .iter()
+        .filter(|path| path.extension().is_some_and(|ext| PATCH_EXTENSIONS.contains(&ext)))
+        .filter_map(|path| path.file_stem())
+        .filter(|sheet| !is_cobalt_book(sheet))
+        .filter_map(|sheet| {
+            // The same sheet can be patched by multiple formats, only schedule it once
+            if premerged.contains_key(&sheet.to_lowercase()) {
+                return None;
+            }
+
+            let (sender, receiver) = mpsc::channel();
+            premerged.insert(sheet.to_lowercase(), receiver);
+            Some((sheet.to_string(), sender))
+        })
+        .collect();
+
+    if queue.is_empty() {
+        return;
+    }
+
+    println!("Pre-merging {} patched sheets", queue.len());
+
+    let queue = Arc::new(Mutex::new(queue));
+
+    for idx in 0..WORKER_COUNT {
+        let queue = queue.clone();
+
+        let _worker = std::thread::Builder::new()
+            .name(format!("premerge_{}", idx))
+            .stack_size(0x100000)
+            .spawn(move || {
+                // Keep grabbing sheets until none are left
+                loop {
+                    let Some((sheet, sender)) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+
+                    let timer = std::time::Instant::now();
+                    let result = premerge_sheet(&sheet);
+
+                    println!("Pre-merging {} on worker {} took {}ms", sheet, idx, timer.elapsed().as_millis());
+
+                    // The import hook might not care anymore, we don't mind.
+                    let _ = sender.send(result);
+                }
+            });
+    }
+}
+
+/// Get the pre-merged version of a sheet, if one was scheduled and was merged on top of `base`, the XML the game is importing.
+///
+/// If the sheet is still being merged, this waits for the worker to be done with it.
+/// Returns None if the sheet was never scheduled, the worker could not merge it or merged it on top of something else,
+/// in which case the caller should merge inline.
+pub fn take_premerged(sheet: &str, base: &[u8]) -> Option<String> {
+    // Don't hold the lock while waiting on the worker
+    let receiver = PREMERGED.lock().unwrap().remove(&sheet.to_lowercase())?;
+
+    let (base_hash, merged) = receiver.recv().ok().flatten()?;
+
+    if base_hash != hash_base(base) {
+        println!("The {} sheet the game imports is not the one that was pre-merged, it will be merged again", sheet);
+        return None;
+    }
+
+    Some(merged)
+}
+
+/// Forget about every sheet that hasn't been claimed yet, so reloading the database merges them inline again.
+pub fn clear_premerged() {
+    PREMERGED.lock().unwrap().clear();
+}
+
+fn premerge_sheet(sheet: &str) -> Option<PremergedSheet> {
+    let Some(base) = load_base_sheet(sheet) else {
+        println!("Could not read the base {} sheet, it will be merged when imported", sheet);
+        return None;
+    };
+
+    let merged = crate::merge_str(&base, &format!("patches/xml/{}.xml", sheet))?;
+
+    Some((hash_base(base.as_bytes()), merged))
+}
+
+/// Hash the base XML of a sheet, to make sure the game imports the one it was pre-merged on top of.
+fn hash_base(base: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(base);
+    hasher.finish()
+}
+
+/// Path of the bundle holding the XML of a sheet, relative to the root of the game files.
+///
+/// The game keeps every sheet in its own bundle named after the lowercase name of the sheet, like `fe_assets_gamedata/item.xml.bundle` for `Item`.
+/// If a sheet ever comes from somewhere else, the pre-merged result won't match what the game imports and the sheet is merged inline instead.
+fn base_sheet_bundle_path(sheet: &str) -> Utf8PathBuf {
+    Utf8PathBuf::from("Data/StreamingAssets/aa/Switch/fe_assets_gamedata").join(format!("{}.xml.bundle", sheet.to_lowercase()))
+}
+
+/// Read the XML for a sheet from the gamedata bundle, preferring a modded copy of the bundle if there is one.
+fn load_base_sheet(sheet: &str) -> Option<String> {
+    let bundle_path = base_sheet_bundle_path(sheet);
+
+    let file = match mods::manager::Manager::get().get_file(&bundle_path) {
+        Ok(file) => file,
+        Err(_) => std::fs::read(format!("rom:/{}", bundle_path)).ok()?,
+    };
+
+    let mut bundle = astra_formats::TextBundle::from_slice(&file).ok()?;
+    let raw = bundle.take_raw().ok()?;
+
+    String::from_utf8(raw).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base_sheet_bundle_path, is_cobalt_book};
+
+    #[test]
+    fn sheets_are_read_from_their_own_bundle() {
+        assert_eq!(base_sheet_bundle_path("Item"), "Data/StreamingAssets/aa/Switch/fe_assets_gamedata/item.xml.bundle");
+        assert_eq!(base_sheet_bundle_path("GodGrowthData"), "Data/StreamingAssets/aa/Switch/fe_assets_gamedata/godgrowthdata.xml.bundle");
+    }
+
+    #[test]
+    fn books_merged_by_cobalt_are_not_premerged() {
+        assert!(is_cobalt_book("Support"));
+        assert!(is_cobalt_book("vibrationevent"));
+        assert!(!is_cobalt_book("Item"));
+    }
+}
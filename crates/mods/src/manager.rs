@@ -18,7 +18,26 @@ pub struct ModConfig {
     pub(crate) author: String,
     #[serde(default)]
     pub(crate) dependencies: Vec<String>,
-    pub(crate) repository: Option<String>
+    pub(crate) repository: Option<String>,
+    #[serde(default)]
+    pub(crate) addressables: AddressablesConfig,
+}
+
+impl ModConfig {
+    pub fn addressables(&self) -> &AddressablesConfig {
+        &self.addressables
+    }
+}
+
+/// How the bundles of a mod are registered in the game's Catalog.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct AddressablesConfig {
+    /// Extra prefixes to strip from container paths, for bundles built from a different Unity project layout.
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    /// Extra extensions to strip from container paths.
+    #[serde(default)]
+    pub suffixes: Vec<String>,
 }
 
 pub struct ModPair {
@@ -364,6 +383,41 @@ impl Manager {
         Ok(root.to_path_buf().join(key))
     }
 
+    /// Get the path of the file on the SD card, for APIs that need to open it themselves.
+    ///
+    /// Returns `None` if the mod providing the file is zipped, in which case [`Manager::get_file`] has to be used instead.
+    pub fn get_real_path(&self, key: impl AsRef<Utf8Path>) -> Result<Option<Utf8PathBuf>, ModError> {
+        let key = key.as_ref();
+
+        let hash = hash(key);
+
+        let index = self.lookup.get_vec(&hash).ok_or(ModError::MissingFile)?;
+
+        Ok(self.vfs[index[0]].real_path(key))
+    }
+
+    /// Get the root of the mod that provides a file, which is either a directory or a zip.
+    pub fn get_mod_root(&self, key: impl AsRef<Utf8Path>) -> Result<&Utf8Path, ModError> {
+        let hash = hash(key);
+
+        let index = self.lookup.get_vec(&hash).ok_or(ModError::MissingFile)?;
+
+        Ok(self.vfs[index[0]].get_root())
+    }
+
+    /// Get the configuration of the mod that provides a file, or the default one if that mod doesn't have a config.yaml.
+    pub fn get_mod_config(&self, key: impl AsRef<Utf8Path>) -> Result<ModConfig, ModError> {
+        let hash = hash(key);
+
+        let index = self.lookup.get_vec(&hash).ok_or(ModError::MissingFile)?;
+
+        match self.vfs[index[0]].get_config() {
+            Err(ModError::ConfigError(err)) => Err(ModError::ConfigError(err)),
+            Err(_) => Ok(ModConfig::default()),
+            config => config,
+        }
+    }
+
     pub fn get_full_path_original(&self, key: impl AsRef<str>) -> Result<Utf8PathBuf, ModError> {
         self.interner.try_get(hash(key.as_ref())).ok_or(ModError::MissingFile)
     }
@@ -384,6 +438,16 @@ impl Manager {
         self.vfs[index[0]].last_modified(key)
     }
 
+    /// Get the uncompressed size of a file without loading it.
+    pub fn get_file_size(&self, key: impl AsRef<Utf8Path>) -> Result<u64, ModError> {
+        let key = key.as_ref();
+        let hash = hash(key);
+
+        let index = self.lookup.get_vec(&hash).ok_or(ModError::MissingFile)?;
+
+        self.vfs[index[0]].file_size(key)
+    }
+
     pub fn get_file(&self, key: impl AsRef<Utf8Path>) -> Result<Vec<u8>, ModError> {
         let key = key.as_ref();
 
@@ -394,6 +458,24 @@ impl Manager {
         self.vfs[index[0]].load(key)
     }
 
+    /// Stream a file to `read` instead of loading it whole, for formats where only part of the file is needed.
+    ///
+    /// Zipped mods are locked while `read` runs, so it should not access other files.
+    pub fn read_file<R>(&self, key: impl AsRef<Utf8Path>, read: impl FnOnce(&mut dyn std::io::Read) -> R) -> Result<R, ModError> {
+        let key = key.as_ref();
+
+        let hash = hash(key);
+
+        let index = self.lookup.get_vec(&hash).ok_or(ModError::MissingFile)?;
+
+        let mut read = Some(read);
+        let mut result = None;
+
+        self.vfs[index[0]].read(key, &mut |reader| result = read.take().map(|read| read(reader)))?;
+
+        result.ok_or(ModError::MissingFile)
+    }
+
     pub fn get_files(&self, key: impl AsRef<Utf8Path>) -> Result<Vec<Vec<u8>>, ModError> {
         let key = key.as_ref();
 
@@ -417,6 +499,24 @@ impl Manager {
             .collect()
     }
 
+    /// Same as [`Manager::get_files_with_locations`], but also provides the priority of the mod each file comes from.
+    ///
+    /// This is useful to order files with different names but the same purpose, lower priorities are meant to be applied first.
+    pub fn get_files_with_priority(&self, key: impl AsRef<Utf8Path>) -> Result<Vec<(usize, Vec<u8>, &Utf8Path)>, ModError> {
+        let hash = hash(key);
+
+        let path = self.interner.try_get(hash).ok_or(ModError::MissingFile)?;
+
+        self.lookup.get_vec(&hash)
+            .ok_or(ModError::MissingFile)?
+            .iter()
+            .map(|idx| {
+                self.vfs[*idx].load(&path)
+                .map(|file| (*idx, file, self.vfs[*idx].get_root()))
+            })
+            .collect()
+    }
+
     pub fn get_locations(&self) -> impl Iterator<Item = Utf8PathBuf> + '_ {
         self.interner.paths()
     }
@@ -1,4 +1,4 @@
-use std::{fs::File, io::BufReader, sync::RwLock};
+use std::{fs::File, io::{BufReader, Read}, sync::RwLock};
 
 use camino::{Utf8PathBuf, Utf8Path};
 use walkdir::WalkDir;
@@ -11,7 +11,12 @@ pub trait VirtualFS: Sync + Send {
     fn get_root(&self) -> &Utf8Path;
     fn discover(&self) -> Vec<Utf8PathBuf>;
     fn last_modified(&self, relative_path: &Utf8Path) -> Result<u64, ModError>;
+    fn file_size(&self, relative_path: &Utf8Path) -> Result<u64, ModError>;
     fn load(&self, relative_path: &Utf8Path) -> Result<Vec<u8>, ModError>;
+    /// Stream the content of a file to `read`, for formats where only part of the file is needed.
+    fn read(&self, relative_path: &Utf8Path, read: &mut dyn FnMut(&mut dyn Read)) -> Result<(), ModError>;
+    /// Path of the file on the storage, if it can be opened directly without going through [`VirtualFS::load`].
+    fn real_path(&self, relative_path: &Utf8Path) -> Option<Utf8PathBuf>;
 }
 
 impl dyn VirtualFS {
@@ -51,6 +56,13 @@ impl VirtualFS for ModDir {
         std::fs::read(full_path).map_err(ModError::IoError)
     }
 
+    fn read(&self, relative_path: &Utf8Path, read: &mut dyn FnMut(&mut dyn Read)) -> Result<(), ModError> {
+        let full_path = self.root.join(relative_path);
+        let mut file = BufReader::new(File::open(full_path).map_err(ModError::IoError)?);
+        read(&mut file);
+        Ok(())
+    }
+
     fn last_modified(&self, relative_path: &Utf8Path) -> Result<u64, ModError> {
         let full_path = self.root.join(relative_path);
         let mut timestamp = nnsdk::fs::FileTimeStamp::new();
@@ -60,6 +72,15 @@ impl VirtualFS for ModDir {
         Ok(timestamp.modify.time)
     }
 
+    fn file_size(&self, relative_path: &Utf8Path) -> Result<u64, ModError> {
+        let full_path = self.root.join(relative_path);
+        std::fs::metadata(full_path).map(|metadata| metadata.len()).map_err(ModError::IoError)
+    }
+
+    fn real_path(&self, relative_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        Some(self.root.join(relative_path))
+    }
+
     fn get_root(&self) -> &Utf8Path {
         &self.root
     }
@@ -109,6 +130,13 @@ impl VirtualFS for ZippedMod {
         Ok(out_buf)
     }
 
+    fn read(&self, relative_path: &Utf8Path, read: &mut dyn FnMut(&mut dyn Read)) -> Result<(), ModError> {
+        let mut file = self.file.write().unwrap();
+        let mut arc = file.by_name(relative_path.as_str()).map_err(|err| ModError::IoError(err.into()))?;
+        read(&mut arc);
+        Ok(())
+    }
+
     fn last_modified(&self, relative_path: &Utf8Path) -> Result<u64, ModError> {
         let mut file = self.file.write().unwrap();
         let arc = file.by_name(relative_path.as_str()).map_err(|err| ModError::IoError(err.into()))?;
@@ -116,6 +144,17 @@ impl VirtualFS for ZippedMod {
         Ok((datatime.datepart() + datatime.timepart()) as u64)
     }
 
+    fn file_size(&self, relative_path: &Utf8Path) -> Result<u64, ModError> {
+        let mut file = self.file.write().unwrap();
+        let arc = file.by_name(relative_path.as_str()).map_err(|err| ModError::IoError(err.into()))?;
+        Ok(arc.size())
+    }
+
+    fn real_path(&self, _relative_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        // Entries only exist compressed inside of the archive
+        None
+    }
+
     fn get_root(&self) -> &Utf8Path {
         &self.root
     }
@@ -0,0 +1,16 @@
+//! Vibration events and chains as defined in `VibrationEvent.xml`, along with everything needed to play them without the game:
+//! the curves they follow, the mixer combining them, the validation of the XML and a renderer to look at them.
+
+mod soundeventmapping;
+mod vibrationcurve;
+mod vibrationevent;
+mod vibrationmixer;
+mod vibrationrender;
+mod vibrationvalidation;
+
+pub use soundeventmapping::*;
+pub use vibrationcurve::*;
+pub use vibrationevent::*;
+pub use vibrationmixer::*;
+pub use vibrationrender::*;
+pub use vibrationvalidation::*;
@@ -0,0 +1,352 @@
+//! Decide which vibration a sound event plays, from the `SoundEventMappings` sheet of the vibration events.
+//!
+//! Sound events without a matching mapping keep playing the vibration event or chain that shares their name.
+
+use std::fmt;
+use std::str::FromStr;
+
+use astra_derive::Astra;
+use regex::Regex;
+use strum_macros::EnumString;
+
+use xmlpatch::Keyed;
+
+#[derive(Astra, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct SoundEventMappingEntry {
+    /// Only used to identify the mapping, so mods can replace it.
+    #[astra(key = "@Name")]
+    pub name: String,
+    #[astra(key = "@Pattern")]
+    pub pattern: String,
+    /// `Exact` if empty.
+    #[astra(key = "@Match")]
+    pub match_type: Option<String>,
+    #[astra(key = "@EngageOwner")]
+    pub engage_owner: Option<String>,
+    #[astra(key = "@Engaged")]
+    pub engaged: Option<String>,
+    #[astra(key = "@PlayerSide")]
+    pub player_side: Option<String>,
+    #[astra(key = "@Critical")]
+    pub critical: Option<String>,
+    /// Vibration event or chain to run. Nothing is felt if empty.
+    #[astra(key = "@Event")]
+    pub event: Option<String>,
+}
+
+impl Keyed for SoundEventMappingEntry {
+    type Key = String;
+
+    fn key(&self) -> Self::Key {
+        self.name.to_owned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumString, PartialEq)]
+pub enum MatchType {
+    Exact,
+    Prefix,
+    /// The whole sound event name has to match the expression.
+    Regex,
+}
+
+#[derive(Debug, Clone)]
+pub enum SoundEventPattern {
+    Exact(String),
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl SoundEventPattern {
+    pub fn is_match(&self, sound_event: &str) -> bool {
+        match self {
+            SoundEventPattern::Exact(name) => sound_event == name,
+            SoundEventPattern::Prefix(prefix) => sound_event.starts_with(prefix.as_str()),
+            SoundEventPattern::Regex(regex) => regex.is_match(sound_event),
+        }
+    }
+}
+
+/// What the game looks like when a sound event is posted.
+///
+/// Everything is only asked for when a mapping needs it, as some of it can only be known in specific places (like during combat).
+/// Returning `None` means it can't be told right now, in which case mappings that depend on it are skipped.
+pub trait SoundEventContext {
+    /// The selected unit has an Emblem ring equipped.
+    fn is_engage_owner(&self) -> Option<bool>;
+    /// The selected unit is currently engaged.
+    fn is_engaging(&self) -> Option<bool>;
+    /// The sound comes from an attack on the player side.
+    fn is_player_side(&self) -> Option<bool>;
+    /// The sound comes from a critical hit.
+    fn is_critical(&self) -> Option<bool>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// Conditions that have to hold for a mapping to apply. `None` matches anything.
+pub struct SoundEventConditions {
+    pub engage_owner: Option<bool>,
+    pub engaged: Option<bool>,
+    pub player_side: Option<bool>,
+    pub critical: Option<bool>,
+}
+
+impl SoundEventConditions {
+    pub fn is_met(&self, context: &impl SoundEventContext) -> bool {
+        // The context is only asked when the condition is set
+        let holds = |expected: Option<bool>, actual: &dyn Fn() -> Option<bool>| match expected {
+            Some(expected) => actual() == Some(expected),
+            None => true,
+        };
+
+        holds(self.engage_owner, &|| context.is_engage_owner())
+            && holds(self.engaged, &|| context.is_engaging())
+            && holds(self.player_side, &|| context.is_player_side())
+            && holds(self.critical, &|| context.is_critical())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SoundEventMapping {
+    pub name: String,
+    pub pattern: SoundEventPattern,
+    pub conditions: SoundEventConditions,
+    /// `None` silences the sound event.
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseSoundEventMappingError {
+    UnknownMatchType(String),
+    InvalidRegex(String),
+    InvalidCondition { field: &'static str, value: String },
+}
+
+impl fmt::Display for ParseSoundEventMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSoundEventMappingError::UnknownMatchType(value) => write!(f, "unknown match type '{}', expected Exact, Prefix or Regex", value),
+            ParseSoundEventMappingError::InvalidRegex(error) => write!(f, "invalid regex: {}", error),
+            ParseSoundEventMappingError::InvalidCondition { field, value } => {
+                write!(f, "{} is '{}', but should be true, false or empty", field, value)
+            },
+        }
+    }
+}
+
+fn parse_condition(field: &'static str, value: &Option<String>) -> Result<Option<bool>, ParseSoundEventMappingError> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) if value.eq_ignore_ascii_case("true") => Ok(Some(true)),
+        Some(value) if value.eq_ignore_ascii_case("false") => Ok(Some(false)),
+        Some(value) => Err(ParseSoundEventMappingError::InvalidCondition {
+            field,
+            value: value.to_owned(),
+        }),
+    }
+}
+
+impl TryFrom<&SoundEventMappingEntry> for SoundEventMapping {
+    type Error = ParseSoundEventMappingError;
+
+    fn try_from(entry: &SoundEventMappingEntry) -> Result<Self, Self::Error> {
+        let match_type = match entry.match_type.as_deref().filter(|value| !value.is_empty()) {
+            Some(value) => MatchType::from_str(value).map_err(|_| ParseSoundEventMappingError::UnknownMatchType(value.to_owned()))?,
+            None => MatchType::Exact,
+        };
+
+        let pattern = match match_type {
+            MatchType::Exact => SoundEventPattern::Exact(entry.pattern.to_owned()),
+            MatchType::Prefix => SoundEventPattern::Prefix(entry.pattern.to_owned()),
+            MatchType::Regex => Regex::new(&format!("^(?:{})$", entry.pattern))
+                .map(SoundEventPattern::Regex)
+                .map_err(|err| ParseSoundEventMappingError::InvalidRegex(err.to_string()))?,
+        };
+
+        Ok(SoundEventMapping {
+            name: entry.name.to_owned(),
+            pattern,
+            conditions: SoundEventConditions {
+                engage_owner: parse_condition("EngageOwner", &entry.engage_owner)?,
+                engaged: parse_condition("Engaged", &entry.engaged)?,
+                player_side: parse_condition("PlayerSide", &entry.player_side)?,
+                critical: parse_condition("Critical", &entry.critical)?,
+            },
+            event: entry.event.to_owned().filter(|event| !event.is_empty()),
+        })
+    }
+}
+
+/// Find the first mapping, in the order of the sheet, that applies to the sound event.
+pub fn find_sound_event_mapping<'a>(
+    mappings: &'a [SoundEventMapping],
+    sound_event: &str,
+    context: &impl SoundEventContext,
+) -> Option<&'a SoundEventMapping> {
+    mappings
+        .iter()
+        .find(|mapping| mapping.pattern.is_match(sound_event) && mapping.conditions.is_met(context))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::{find_sound_event_mapping, ParseSoundEventMappingError, SoundEventContext, SoundEventMapping, SoundEventMappingEntry};
+
+    #[derive(Default)]
+    struct Context {
+        engage_owner: Option<bool>,
+        engaged: Option<bool>,
+        player_side: Option<bool>,
+        critical: Option<bool>,
+        asked: Cell<usize>,
+    }
+
+    impl SoundEventContext for Context {
+        fn is_engage_owner(&self) -> Option<bool> {
+            self.asked.set(self.asked.get() + 1);
+            self.engage_owner
+        }
+
+        fn is_engaging(&self) -> Option<bool> {
+            self.asked.set(self.asked.get() + 1);
+            self.engaged
+        }
+
+        fn is_player_side(&self) -> Option<bool> {
+            self.asked.set(self.asked.get() + 1);
+            self.player_side
+        }
+
+        fn is_critical(&self) -> Option<bool> {
+            self.asked.set(self.asked.get() + 1);
+            self.critical
+        }
+    }
+
+    fn entry(name: &str, pattern: &str, match_type: &str, conditions: [&str; 4], event: &str) -> SoundEventMappingEntry {
+        let field = |value: &str| Some(value.to_owned());
+
+        SoundEventMappingEntry {
+            name: name.to_owned(),
+            pattern: pattern.to_owned(),
+            match_type: field(match_type),
+            engage_owner: field(conditions[0]),
+            engaged: field(conditions[1]),
+            player_side: field(conditions[2]),
+            critical: field(conditions[3]),
+            event: field(event),
+        }
+    }
+
+    fn mappings(entries: &[SoundEventMappingEntry]) -> Vec<SoundEventMapping> {
+        entries.iter().map(|entry| SoundEventMapping::try_from(entry).unwrap()).collect()
+    }
+
+    fn resolve<'a>(mappings: &'a [SoundEventMapping], sound_event: &str, context: &Context) -> Option<Option<&'a str>> {
+        find_sound_event_mapping(mappings, sound_event, context).map(|mapping| mapping.event.as_deref())
+    }
+
+    #[test]
+    fn patterns_match_exact_prefix_and_regex() {
+        let mappings = mappings(&[
+            entry("Exact", "SE_Hit", "", ["", "", "", ""], "Hit"),
+            entry("Prefix", "SE_Hit_", "Prefix", ["", "", "", ""], "HitVariant"),
+            entry("Regex", r"SE_Swing_\d+", "Regex", ["", "", "", ""], "Swing"),
+        ]);
+        let context = Context::default();
+
+        assert_eq!(resolve(&mappings, "SE_Hit", &context), Some(Some("Hit")));
+        assert_eq!(resolve(&mappings, "SE_Hit_Heavy", &context), Some(Some("HitVariant")));
+        assert_eq!(resolve(&mappings, "SE_Swing_02", &context), Some(Some("Swing")));
+        // Regexes have to match the whole name
+        assert_eq!(resolve(&mappings, "SE_Swing_02_Echo", &context), None);
+        assert_eq!(resolve(&mappings, "SE_Miss", &context), None);
+    }
+
+    #[test]
+    fn unit_selection_follows_the_unit() {
+        let mappings = mappings(&[
+            entry(
+                "UnitTouch_Single",
+                "UnitTouch",
+                "Exact",
+                ["false", "", "", ""],
+                "UnitTouch_NotEngageOwner",
+            ),
+            entry("UnitTouch_Ring", "UnitTouch", "Exact", ["true", "false", "", ""], ""),
+            entry("UnitTouch_Engaged", "UnitTouch", "Exact", ["true", "true", "", ""], "UnitTouch_Engaged"),
+        ]);
+
+        let single = Context {
+            engage_owner: Some(false),
+            ..Default::default()
+        };
+        let ring = Context {
+            engage_owner: Some(true),
+            engaged: Some(false),
+            ..Default::default()
+        };
+        let engaged = Context {
+            engage_owner: Some(true),
+            engaged: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(resolve(&mappings, "UnitTouch", &single), Some(Some("UnitTouch_NotEngageOwner")));
+        // Silenced, the Emblem appearing already vibrates
+        assert_eq!(resolve(&mappings, "UnitTouch", &ring), Some(None));
+        assert_eq!(resolve(&mappings, "UnitTouch", &engaged), Some(Some("UnitTouch_Engaged")));
+    }
+
+    #[test]
+    fn unknown_conditions_skip_the_mapping() {
+        let mappings = mappings(&[
+            entry("Critical", "SE_Hit", "", ["", "", "true", "true"], "CriticalHit"),
+            entry("Hit", "SE_Hit", "", ["", "", "", ""], "Hit"),
+        ]);
+
+        let outside_combat = Context::default();
+        let critical = Context {
+            player_side: Some(true),
+            critical: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(resolve(&mappings, "SE_Hit", &outside_combat), Some(Some("Hit")));
+        assert_eq!(resolve(&mappings, "SE_Hit", &critical), Some(Some("CriticalHit")));
+    }
+
+    #[test]
+    fn context_is_only_asked_when_needed() {
+        let mappings = mappings(&[entry("Hit", "SE_Hit", "", ["", "", "", ""], "Hit")]);
+        let context = Context::default();
+
+        resolve(&mappings, "SE_Hit", &context);
+        resolve(&mappings, "SE_Miss", &context);
+
+        assert_eq!(context.asked.get(), 0);
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        let parse = |entry: SoundEventMappingEntry| SoundEventMapping::try_from(&entry).err();
+
+        assert_eq!(
+            parse(entry("Bad", "SE_Hit", "Glob", ["", "", "", ""], "Hit")),
+            Some(ParseSoundEventMappingError::UnknownMatchType(String::from("Glob")))
+        );
+        assert!(matches!(
+            parse(entry("Bad", "SE_(Hit", "Regex", ["", "", "", ""], "Hit")),
+            Some(ParseSoundEventMappingError::InvalidRegex(_))
+        ));
+        assert_eq!(
+            parse(entry("Bad", "SE_Hit", "", ["", "yes", "", ""], "Hit")),
+            Some(ParseSoundEventMappingError::InvalidCondition {
+                field: "Engaged",
+                value: String::from("yes")
+            })
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use easer::functions::*;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{EasingType, VibrationEvent};
+
+pub struct EasingArgs {
+    /// the current time (or position) of the tween. This can be seconds or frames, steps, seconds, ms, whatever as long as the unit is the same as is used for the total time.
+    pub current_time: f32,
+    /// the beginning value of the property.
+    pub beginning_value: f32,
+    /// chage is the change between the beginning and destination value of the property.
+    pub change: f32,
+    /// total time of the tween.
+    pub total_time: f32,
+}
+
+/// Execute an easing function with the given arguments.
+pub fn execute_easing(easing_type: Option<EasingType>, args: EasingArgs) -> f32 {
+    use EasingType::*;
+    let EasingArgs {
+        current_time: t,
+        beginning_value: b,
+        change: c,
+        total_time: d,
+    } = args;
+    let Some(easing_type) = easing_type else {
+        return b;
+    };
+    match easing_type {
+        EaseInExpo => Expo::ease_in(t, b, c, d),
+        EaseInQuint => Quint::ease_in(t, b, c, d),
+        EaseInQuad => Quad::ease_in(t, b, c, d),
+        EaseInCubic => Cubic::ease_in(t, b, c, d),
+        EaseOutCubic => Cubic::ease_out(t, b, c, d),
+        EaseOutSine => Sine::ease_out(t, b, c, d),
+        EaseInBounce => Bounce::ease_in(t, b, c, d),
+        // Reverse types start from 0.0 and work their way up to the beginning value.
+        ReverseEaseInExpo => Expo::ease_in(t, 0.0, b, d),
+        ReverseEaseInQuint => Quint::ease_in(t, 0.0, b, d),
+        ReverseEaseInQuad => Quad::ease_in(t, 0.0, b, d),
+        ReverseEaseInCubic => Cubic::ease_in(t, 0.0, b, d),
+        ReverseEaseOutCubic => Cubic::ease_out(t, 0.0, b, d),
+        ReverseEaseOutSine => Sine::ease_out(t, 0.0, b, d),
+        ReverseEaseInBounce => Bounce::ease_in(t, 0.0, b, d),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How to get from a keyframe to the next one.
+pub enum KeyframeInterpolation {
+    /// Hold the values of the keyframe until the next one, for sharp cuts.
+    Step,
+    /// Go from one keyframe to the next in a straight line.
+    Linear,
+    /// Use one of the easing presets between the two keyframes.
+    Eased(EasingType),
+}
+
+impl FromStr for KeyframeInterpolation {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Step" => Ok(Self::Step),
+            "Linear" => Ok(Self::Linear),
+            _ => EasingType::from_str(value).map(Self::Eased).map_err(|_| ()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// State of both motors at a given point of a vibration.
+pub struct VibrationSample {
+    pub amp_low: f32,
+    pub amp_high: f32,
+    pub freq_low: f32,
+    pub freq_high: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A point of a vibration curve.
+///
+/// Written as `Time,AmpLow,AmpHigh,FreqLow,FreqHigh,Interpolation` in the `@Keyframes` column, one keyframe per item.
+/// The interpolation is optional, defaults to `Linear` and is used for the segment going to the next keyframe.
+pub struct VibrationKeyframe {
+    /// Time in seconds since the start of the event.
+    pub time: f32,
+    pub sample: VibrationSample,
+    pub interpolation: KeyframeInterpolation,
+}
+
+pub enum ParseKeyframeError {
+    MissingValues(String),
+    InvalidNumber(String),
+    UnknownInterpolation(String),
+}
+
+impl fmt::Display for ParseKeyframeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseKeyframeError::MissingValues(value) => write!(f, "Keyframe needs at least Time,AmpLow,AmpHigh,FreqLow,FreqHigh: {}", value),
+            ParseKeyframeError::InvalidNumber(value) => write!(f, "Could not parse a keyframe value to f32: {}", value),
+            ParseKeyframeError::UnknownInterpolation(value) => write!(f, "Unknown keyframe interpolation: {}", value),
+        }
+    }
+}
+
+pub fn parse_keyframe(keyframe_string: &str) -> Result<VibrationKeyframe, ParseKeyframeError> {
+    use ParseKeyframeError::*;
+    let values: Vec<&str> = keyframe_string.split(",").map(str::trim).collect();
+
+    if values.len() < 5 {
+        return Err(MissingValues(keyframe_string.to_owned()));
+    }
+
+    let Ok(numbers) = values[..5].iter().map(|value| value.parse::<f32>()).collect::<Result<Vec<_>, _>>() else {
+        return Err(InvalidNumber(keyframe_string.to_owned()));
+    };
+
+    let interpolation = match values.get(5) {
+        Some(value) if !value.is_empty() => KeyframeInterpolation::from_str(value).map_err(|_| UnknownInterpolation(keyframe_string.to_owned()))?,
+        _ => KeyframeInterpolation::Linear,
+    };
+
+    Ok(VibrationKeyframe {
+        time: numbers[0],
+        sample: VibrationSample {
+            amp_low: numbers[1],
+            amp_high: numbers[2],
+            freq_low: numbers[3],
+            freq_high: numbers[4],
+        },
+        interpolation,
+    })
+}
+
+/// Get the state of the motors at `time` seconds into a keyframed vibration.
+///
+/// Keyframes are expected to be sorted by time. Before the first keyframe and after the last one, their values are held.
+pub fn sample_keyframes(keyframes: &[VibrationKeyframe], time: f32) -> Option<VibrationSample> {
+    let first = keyframes.first()?;
+
+    if time <= first.time {
+        return Some(first.sample);
+    }
+
+    let Some(idx) = keyframes.windows(2).position(|pair| time < pair[1].time) else {
+        return keyframes.last().map(|keyframe| keyframe.sample);
+    };
+
+    let (from, to) = (&keyframes[idx], &keyframes[idx + 1]);
+
+    let interpolate = |start: f32, end: f32| {
+        let args = EasingArgs {
+            current_time: time - from.time,
+            beginning_value: start,
+            change: end - start,
+            total_time: to.time - from.time,
+        };
+
+        match from.interpolation {
+            KeyframeInterpolation::Step => start,
+            KeyframeInterpolation::Linear => Linear::ease_in(args.current_time, args.beginning_value, args.change, args.total_time),
+            KeyframeInterpolation::Eased(easing_type) => execute_easing(Some(easing_type), args),
+        }
+    };
+
+    Some(VibrationSample {
+        amp_low: interpolate(from.sample.amp_low, to.sample.amp_low),
+        amp_high: interpolate(from.sample.amp_high, to.sample.amp_high),
+        freq_low: interpolate(from.sample.freq_low, to.sample.freq_low),
+        freq_high: interpolate(from.sample.freq_high, to.sample.freq_high),
+    })
+}
+
+impl VibrationEvent {
+    /// Whether the event changes over time, through easing or keyframes.
+    pub fn is_animated(&self) -> bool {
+        self.easing_type.is_some() || !self.keyframes.is_empty()
+    }
+
+    /// Get the state of the motors at `time` seconds into the event, or None if the event isn't playing at that time.
+    ///
+    /// Eased events go from their amplitudes down to zero over their duration, keyframed events follow their keyframes.
+    pub fn sample(&self, time: f32) -> Option<VibrationSample> {
+        if !(0.0..self.time).contains(&time) {
+            return None;
+        }
+
+        if !self.keyframes.is_empty() {
+            return sample_keyframes(&self.keyframes, time);
+        }
+
+        let ease = |value: f32| {
+            execute_easing(
+                self.easing_type,
+                EasingArgs {
+                    current_time: time,
+                    beginning_value: value,
+                    change: -value, // go to zero
+                    total_time: self.time,
+                },
+            )
+        };
+
+        Some(VibrationSample {
+            amp_low: ease(self.amp_low),
+            amp_high: ease(self.amp_high),
+            freq_low: self.freq_low,
+            freq_high: self.freq_high,
+        })
+    }
+
+    /// Copy of the event that holds the state it is in at `time`, without easing or keyframes.
+    pub fn step(&self, time: f32) -> Option<VibrationEvent> {
+        let sample = self.sample(time)?;
+
+        Some(VibrationEvent {
+            amp_low: sample.amp_low,
+            amp_high: sample.amp_high,
+            freq_low: sample.freq_low,
+            freq_high: sample.freq_high,
+            easing_type: None,
+            keyframes: vec![],
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_keyframe, sample_keyframes, KeyframeInterpolation, VibrationKeyframe};
+
+    fn keyframes(items: &[&str]) -> Vec<VibrationKeyframe> {
+        items.iter().map(|item| parse_keyframe(item).ok().unwrap()).collect()
+    }
+
+    #[test]
+    fn interpolation_defaults_to_linear() {
+        let keyframe = parse_keyframe("0.5,0.1,0.2,160,320").ok().unwrap();
+
+        assert_eq!(keyframe.time, 0.5);
+        assert_eq!(keyframe.interpolation, KeyframeInterpolation::Linear);
+        assert!(parse_keyframe("0.5,0.1,0.2").is_err());
+        assert!(parse_keyframe("0.5,0.1,0.2,160,320,Wobbly").is_err());
+    }
+
+    #[test]
+    fn linear_segments_are_interpolated() {
+        let keyframes = keyframes(&["0,0,0,100,200", "1,1,0.5,200,400"]);
+
+        let sample = sample_keyframes(&keyframes, 0.5).unwrap();
+
+        assert_eq!((sample.amp_low, sample.amp_high, sample.freq_low, sample.freq_high), (0.5, 0.25, 150.0, 300.0));
+    }
+
+    #[test]
+    fn step_segments_hold_until_the_next_keyframe() {
+        // Ramp up, then snap back to nothing
+        let keyframes = keyframes(&["0,0,0,160,320,EaseInQuad", "0.3,0.8,0.8,160,320,Step", "0.35,0,0,160,320"]);
+
+        assert_eq!(sample_keyframes(&keyframes, 0.34).unwrap().amp_low, 0.8);
+        assert_eq!(sample_keyframes(&keyframes, 0.35).unwrap().amp_low, 0.0);
+        assert!(sample_keyframes(&keyframes, 0.15).unwrap().amp_low < 0.4);
+    }
+
+    #[test]
+    fn values_are_held_outside_of_the_keyframes() {
+        let keyframes = keyframes(&["0.1,0.2,0.2,160,320", "0.2,0.4,0.4,160,320"]);
+
+        assert_eq!(sample_keyframes(&keyframes, 0.0).unwrap().amp_low, 0.2);
+        assert_eq!(sample_keyframes(&keyframes, 1.0).unwrap().amp_low, 0.4);
+        assert!(sample_keyframes(&[], 0.0).is_none());
+    }
+}
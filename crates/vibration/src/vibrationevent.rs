@@ -6,6 +6,10 @@ use strum_macros::EnumString;
 
 use ordered_float::OrderedFloat;
 
+use xmlpatch::{Keyed, XmlPatch};
+
+use super::{parse_keyframe, SoundEventMappingEntry, VibrationKeyframe};
+
 use std::cmp::Eq;
 use std::ops::Add;
 
@@ -13,6 +17,26 @@ use std::ops::Add;
 pub struct VibrationEventBook {
     pub vibration_events: Sheet<Vec<VibrationEventEntry>>,
     pub vibration_event_chains: Sheet<Vec<VibrationEventChainEntry>>,
+    pub sound_event_mappings: Sheet<Vec<SoundEventMappingEntry>>,
+}
+
+xmlpatch::impl_xml_patch!(VibrationEventBook {
+    vibration_events,
+    vibration_event_chains,
+    sound_event_mappings
+});
+
+impl VibrationEventBook {
+    /// Replace the rows with the same name as the ones in `book` and add the others, even if they are identical to the bundled ones.
+    pub fn replace_rows(&mut self, book: Self) {
+        // Patching against nothing means no row of the book is considered unchanged
+        let mut empty = book.clone();
+        empty.vibration_events.data.clear();
+        empty.vibration_event_chains.data.clear();
+        empty.sound_event_mappings.data.clear();
+
+        self.patch(book, &empty);
+    }
 }
 
 #[derive(Astra, Debug, Eq, PartialEq, Hash, Clone)]
@@ -44,6 +68,24 @@ pub struct VibrationEventEntry {
     pub easing: Option<String>,
     #[astra(key = "@VibrationType")]
     pub vibration_type: Option<String>,
+    #[astra(key = "@Keyframes")]
+    pub keyframes: Vec<String>,
+}
+
+impl Keyed for VibrationEventChainEntry {
+    type Key = String;
+
+    fn key(&self) -> Self::Key {
+        self.name.to_owned()
+    }
+}
+
+impl Keyed for VibrationEventEntry {
+    type Key = String;
+
+    fn key(&self) -> Self::Key {
+        self.name.to_owned()
+    }
 }
 
 #[derive(Debug, Clone, Copy, EnumString, PartialEq)]
@@ -66,7 +108,7 @@ pub enum EasingType {
     ReverseEaseInBounce,
 }
 
-#[derive(Debug, Clone, Copy, EnumString, PartialEq)]
+#[derive(Debug, Clone, Copy, EnumString, PartialEq, Eq, Hash)]
 pub enum VibrationType {
     Combat,
     MapCombat,
@@ -89,6 +131,8 @@ pub struct VibrationEvent {
     pub freq_high: f32,
     pub easing_type: Option<EasingType>,
     pub vibration_type: Option<VibrationType>,
+    /// Curve to follow instead of the amplitudes and frequencies above. The event lasts until the last keyframe.
+    pub keyframes: Vec<VibrationKeyframe>,
 }
 
 #[derive(Debug, Clone)]
@@ -110,9 +154,28 @@ pub const FREQ_HIGH: f32 = 300.0;
 
 impl From<VibrationEventEntry> for VibrationEvent {
     fn from(item: VibrationEventEntry) -> Self {
+        let mut keyframes: Vec<VibrationKeyframe> = item
+            .keyframes
+            .iter()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| parse_keyframe(x))
+            .filter_map(|x| match x {
+                Ok(x) => Some(x),
+                Err(e) => {
+                    println!("Failed to parse keyframe: {}", e);
+                    None
+                },
+            })
+            .collect();
+
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
         VibrationEvent {
             name: item.name,
-            time: item.time.unwrap_or(OrderedFloat(0.0)).into_inner(),
+            time: match keyframes.last() {
+                Some(keyframe) => keyframe.time,
+                None => item.time.unwrap_or(OrderedFloat(0.0)).into_inner(),
+            },
             amplitude_magnitude: item.amplitude_magnitude.unwrap_or(OrderedFloat(0.0)).into_inner(),
             amp_low: item.amp_low.unwrap_or(OrderedFloat(0.0)).into_inner(),
             amp_high: item.amp_high.unwrap_or(OrderedFloat(0.0)).into_inner(),
@@ -132,6 +195,7 @@ impl From<VibrationEventEntry> for VibrationEvent {
                     None
                 },
             }),
+            keyframes,
         }
     }
 }
@@ -175,7 +239,7 @@ impl From<VibrationEventChainEntry> for VibrationEventChain {
     }
 }
 
-enum ParseEventChainError {
+pub(crate) enum ParseEventChainError {
     InvalidChainString(String),
     InvalidChainStringMissingComma(String),
     InvalidChainStringParseFloat(String),
@@ -191,7 +255,7 @@ impl fmt::Display for ParseEventChainError {
     }
 }
 
-fn parse_event_chain(chain_string: &String) -> Result<VibrationEventChainItem, ParseEventChainError> {
+pub(crate) fn parse_event_chain(chain_string: &String) -> Result<VibrationEventChainItem, ParseEventChainError> {
     use ParseEventChainError::*;
     let mut split = chain_string.split(",");
     let Some(name) = split.next() else {
@@ -224,6 +288,7 @@ impl Add for VibrationEvent {
             freq_high: self.freq_high + rhs.freq_high,
             easing_type: self.easing_type,
             vibration_type: self.vibration_type,
+            keyframes: self.keyframes,
         }
     }
 }
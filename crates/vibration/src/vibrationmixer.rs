@@ -0,0 +1,189 @@
+use strum_macros::{Display, EnumString};
+
+use super::{VibrationEvent, VibrationSample, AMPLITUDE_MAX};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumString, Display)]
+/// How the amplitudes of overlapping vibrations are combined.
+pub enum MixMode {
+    /// The strongest vibration on each motor wins.
+    #[default]
+    Max,
+    /// Vibrations add up, up to [`AMPLITUDE_MAX`].
+    Sum,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveVibration {
+    voice: usize,
+    event: VibrationEvent,
+    start: f32,
+    end: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What the motors should be doing once every active vibration has been combined.
+pub struct MixedVibration {
+    pub amplitude_magnitude: f32,
+    pub sample: VibrationSample,
+    /// Game time at which the last of the combined vibrations ends.
+    pub end: f32,
+}
+
+#[derive(Debug, Default)]
+/// Keeps track of every vibration that is currently playing so they can be felt at the same time.
+///
+/// Vibrations are played on a voice. Playing a vibration on a voice that is already in use replaces what it was playing,
+/// which is how eased and keyframed events move from one step to the next without piling up.
+pub struct VibrationMixer {
+    pub mode: MixMode,
+    active: Vec<ActiveVibration>,
+    applied: Option<MixedVibration>,
+}
+
+impl VibrationMixer {
+    pub fn new(mode: MixMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Start playing a vibration at `start` for as long as its `time`.
+    pub fn play(&mut self, voice: usize, event: VibrationEvent, start: f32) {
+        let vibration = ActiveVibration {
+            voice,
+            end: start + event.time,
+            event,
+            start,
+        };
+
+        match self.active.iter_mut().find(|active| active.voice == voice) {
+            Some(active) => *active = vibration,
+            None => self.active.push(vibration),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+        self.applied = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Forget the vibrations that are over, then combine the ones that are playing at `now`.
+    pub fn mix(&mut self, now: f32) -> Option<MixedVibration> {
+        self.active.retain(|active| active.end > now);
+
+        let playing: Vec<&VibrationEvent> = self.active.iter().filter(|active| active.start <= now).map(|active| &active.event).collect();
+
+        let end = self.active.iter().filter(|active| active.start <= now).map(|active| active.end).reduce(f32::max)?;
+
+        // Each motor takes its frequency from the vibration that contributes the most to it
+        let loudest_low = playing.iter().max_by(|a, b| a.amp_low.total_cmp(&b.amp_low))?;
+        let loudest_high = playing.iter().max_by(|a, b| a.amp_high.total_cmp(&b.amp_high))?;
+
+        let combine = |value: fn(&VibrationEvent) -> f32| match self.mode {
+            MixMode::Max => playing.iter().map(|event| value(event)).fold(0.0, f32::max),
+            MixMode::Sum => playing.iter().map(|event| value(event)).sum::<f32>().clamp(0.0, AMPLITUDE_MAX),
+        };
+
+        Some(MixedVibration {
+            amplitude_magnitude: combine(|event| event.amplitude_magnitude),
+            sample: VibrationSample {
+                amp_low: combine(|event| event.amp_low),
+                amp_high: combine(|event| event.amp_high),
+                freq_low: loudest_low.freq_low,
+                freq_high: loudest_high.freq_high,
+            },
+            end,
+        })
+    }
+
+    /// Same as [`VibrationMixer::mix`], but only returns something if the motors need to be told something new since the last call.
+    pub fn next_change(&mut self, now: f32) -> Option<MixedVibration> {
+        let mixed = self.mix(now);
+
+        if mixed == self.applied {
+            return None;
+        }
+
+        self.applied = mixed;
+        mixed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MixMode, VibrationMixer};
+    use crate::VibrationEvent;
+
+    fn event(time: f32, amp_low: f32, amp_high: f32, freq_low: f32) -> VibrationEvent {
+        VibrationEvent {
+            name: String::from("Test"),
+            time,
+            amplitude_magnitude: 0.5,
+            amp_low,
+            amp_high,
+            freq_low,
+            freq_high: 320.0,
+            easing_type: None,
+            vibration_type: None,
+            keyframes: vec![],
+        }
+    }
+
+    #[test]
+    fn overlapping_vibrations_are_all_felt() {
+        let mut mixer = VibrationMixer::new(MixMode::Max);
+
+        mixer.play(0, event(1.0, 0.2, 0.6, 100.0), 0.0);
+        mixer.play(1, event(0.5, 0.4, 0.1, 200.0), 0.1);
+
+        let mixed = mixer.mix(0.2).unwrap();
+
+        assert_eq!((mixed.sample.amp_low, mixed.sample.amp_high), (0.4, 0.6));
+        assert_eq!(mixed.sample.freq_low, 200.0);
+        assert_eq!(mixed.end, 1.0);
+    }
+
+    #[test]
+    fn sums_are_clamped() {
+        let mut mixer = VibrationMixer::new(MixMode::Sum);
+
+        mixer.play(0, event(1.0, 2.0, 0.25, 100.0), 0.0);
+        mixer.play(1, event(1.0, 2.0, 0.25, 100.0), 0.0);
+
+        let mixed = mixer.mix(0.5).unwrap();
+
+        assert_eq!((mixed.amplitude_magnitude, mixed.sample.amp_low, mixed.sample.amp_high), (1.0, 3.0, 0.5));
+    }
+
+    #[test]
+    fn vibrations_expire_and_voices_are_replaced() {
+        let mut mixer = VibrationMixer::new(MixMode::Max);
+
+        mixer.play(0, event(10.0, 0.5, 0.5, 100.0), 0.0);
+        // Next step of the same eased event
+        mixer.play(0, event(0.1, 0.25, 0.25, 100.0), 0.5);
+
+        assert_eq!(mixer.mix(0.55).unwrap().sample.amp_low, 0.25);
+        assert!(mixer.mix(0.7).is_none());
+        assert!(mixer.is_empty());
+    }
+
+    #[test]
+    fn unchanged_mixes_are_only_reported_once() {
+        let mut mixer = VibrationMixer::new(MixMode::Max);
+
+        mixer.play(0, event(1.0, 0.5, 0.5, 100.0), 0.0);
+
+        assert!(mixer.next_change(0.1).is_some());
+        assert!(mixer.next_change(0.2).is_none());
+
+        mixer.play(1, event(1.0, 0.75, 0.5, 100.0), 0.3);
+
+        assert!(mixer.next_change(0.3).is_some());
+    }
+}
@@ -0,0 +1,259 @@
+//! Render vibration events to a timeline, so they can be reviewed and tested without a Switch in hand.
+//!
+//! Events are sampled with the same easing and keyframe code the game uses, and chains are combined with the same mixer.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use image::{Rgb, RgbImage};
+
+use super::{MixMode, VibrationEvent, VibrationEventChain, VibrationMixer, VibrationSample, AMPLITUDE_MAX, FREQUENCY_MAX};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// State of the motors at a point of the timeline.
+pub struct TimelinePoint {
+    /// Time in seconds since the start of the render.
+    pub time: f32,
+    pub amplitude_magnitude: f32,
+    /// Silent if nothing is playing.
+    pub sample: VibrationSample,
+}
+
+const SILENCE: VibrationSample = VibrationSample {
+    amp_low: 0.0,
+    amp_high: 0.0,
+    freq_low: 0.0,
+    freq_high: 0.0,
+};
+
+/// Sample a single event `samples_per_second` times per second over its duration.
+pub fn render_event(event: &VibrationEvent, samples_per_second: u32) -> Vec<TimelinePoint> {
+    render(&[(0.0, event)], MixMode::Max, samples_per_second)
+}
+
+/// Sample a chain `samples_per_second` times per second, until its last event is over.
+///
+/// Events are looked up in `events` like the game does, and the ones that are missing are skipped.
+pub fn render_chain(
+    chain: &VibrationEventChain,
+    events: &HashMap<String, VibrationEvent>,
+    mode: MixMode,
+    samples_per_second: u32,
+) -> Vec<TimelinePoint> {
+    let items: Vec<(f32, &VibrationEvent)> = chain
+        .chain
+        .iter()
+        .filter_map(|item| events.get(&item.name).map(|event| (item.delay, event)))
+        .collect();
+
+    render(&items, mode, samples_per_second)
+}
+
+fn render(items: &[(f32, &VibrationEvent)], mode: MixMode, samples_per_second: u32) -> Vec<TimelinePoint> {
+    let length = items.iter().map(|(delay, event)| delay + event.time).fold(0.0, f32::max);
+    // Rounded so float imprecision doesn't add a silent sample at the end
+    let sample_count = (length * samples_per_second as f32).round() as usize;
+
+    (0..sample_count)
+        .map(|idx| {
+            let time = idx as f32 / samples_per_second as f32;
+
+            // Every event on its own voice, holding the state it's in at this point
+            let mut mixer = VibrationMixer::new(mode);
+
+            items.iter().enumerate().for_each(|(voice, (delay, event))| {
+                if let Some(step) = event.step(time - delay) {
+                    mixer.play(voice, step, *delay);
+                }
+            });
+
+            match mixer.mix(time) {
+                Some(mixed) => TimelinePoint {
+                    time,
+                    amplitude_magnitude: mixed.amplitude_magnitude,
+                    sample: mixed.sample,
+                },
+                None => TimelinePoint {
+                    time,
+                    amplitude_magnitude: 0.0,
+                    sample: SILENCE,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Write the timeline as CSV, one row per point.
+pub fn to_csv(timeline: &[TimelinePoint]) -> String {
+    let mut out = String::from("time,amplitude_magnitude,amp_low,freq_low,amp_high,freq_high\n");
+
+    timeline.iter().for_each(|point| {
+        let _ = writeln!(
+            out,
+            "{:.4},{},{},{},{},{}",
+            point.time, point.amplitude_magnitude, point.sample.amp_low, point.sample.freq_low, point.sample.amp_high, point.sample.freq_high
+        );
+    });
+
+    out
+}
+
+const PLOT_HEIGHT: u32 = 128;
+const PLOT_MARGIN: u32 = 8;
+
+const BACKGROUND: Rgb<u8> = Rgb([24, 24, 32]);
+const AXIS: Rgb<u8> = Rgb([64, 64, 80]);
+const LOW_MOTOR: Rgb<u8> = Rgb([230, 90, 70]);
+const HIGH_MOTOR: Rgb<u8> = Rgb([80, 160, 240]);
+
+/// Where to draw a value, the value it's scaled against, how to get it and which color to use.
+type Plot = (u32, f32, fn(&VibrationSample) -> f32, Rgb<u8>);
+
+/// Plot the timeline, one pixel column per point.
+///
+/// The top half has the amplitudes and the bottom half the frequencies, with the low motor in red and the high motor in blue.
+pub fn to_png(timeline: &[TimelinePoint]) -> RgbImage {
+    let width = (timeline.len() as u32).max(1) + PLOT_MARGIN * 2;
+    let height = PLOT_HEIGHT * 2 + PLOT_MARGIN * 3;
+
+    let mut image = RgbImage::from_pixel(width, height, BACKGROUND);
+
+    let amplitude_top = PLOT_MARGIN;
+    let frequency_top = PLOT_HEIGHT + PLOT_MARGIN * 2;
+
+    // Baselines
+    (0..width).for_each(|x| {
+        image.put_pixel(x, amplitude_top + PLOT_HEIGHT - 1, AXIS);
+        image.put_pixel(x, frequency_top + PLOT_HEIGHT - 1, AXIS);
+    });
+
+    let plots: [Plot; 4] = [
+        (amplitude_top, AMPLITUDE_MAX, |sample| sample.amp_low, LOW_MOTOR),
+        (amplitude_top, AMPLITUDE_MAX, |sample| sample.amp_high, HIGH_MOTOR),
+        (frequency_top, FREQUENCY_MAX, |sample| sample.freq_low, LOW_MOTOR),
+        (frequency_top, FREQUENCY_MAX, |sample| sample.freq_high, HIGH_MOTOR),
+    ];
+
+    plots.into_iter().for_each(|(top, max, value, color)| {
+        let to_y = |point: &TimelinePoint| {
+            let ratio = (value(&point.sample) / max).clamp(0.0, 1.0);
+            top + PLOT_HEIGHT - 1 - (ratio * (PLOT_HEIGHT - 1) as f32).round() as u32
+        };
+
+        let mut previous_y = None;
+
+        timeline.iter().enumerate().for_each(|(idx, point)| {
+            let x = PLOT_MARGIN + idx as u32;
+            let y = to_y(point);
+
+            // Fill the gap with the previous point so sudden changes still look like a line
+            let (from, to) = match previous_y {
+                Some(previous_y) if previous_y < y => (previous_y, y),
+                Some(previous_y) => (y, previous_y),
+                None => (y, y),
+            };
+
+            (from..=to).for_each(|y| image.put_pixel(x, y, color));
+
+            previous_y = Some(y);
+        });
+    });
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{render_chain, render_event, to_csv, to_png, PLOT_HEIGHT, PLOT_MARGIN};
+    use crate::{parse_keyframe, EasingType, MixMode, VibrationEvent, VibrationEventChain, VibrationEventChainItem};
+
+    fn event(name: &str, time: f32, amp: f32) -> VibrationEvent {
+        VibrationEvent {
+            name: name.to_string(),
+            time,
+            amplitude_magnitude: 0.5,
+            amp_low: amp,
+            amp_high: amp,
+            freq_low: 160.0,
+            freq_high: 320.0,
+            easing_type: None,
+            vibration_type: None,
+            keyframes: vec![],
+        }
+    }
+
+    #[test]
+    fn plain_events_hold_their_values() {
+        let timeline = render_event(&event("Hit", 0.5, 0.25), 100);
+
+        assert_eq!(timeline.len(), 50);
+        assert!(timeline.iter().all(|point| point.sample.amp_low == 0.25 && point.sample.freq_high == 320.0));
+    }
+
+    #[test]
+    fn eased_events_fade_out() {
+        let mut eased = event("Fade", 1.0, 0.5);
+        eased.easing_type = Some(EasingType::EaseInQuad);
+
+        let timeline = render_event(&eased, 30);
+
+        assert_eq!(timeline[0].sample.amp_low, 0.5);
+        assert!(timeline.windows(2).all(|pair| pair[1].sample.amp_low <= pair[0].sample.amp_low));
+        assert!(timeline.last().unwrap().sample.amp_low < 0.05);
+    }
+
+    #[test]
+    fn keyframed_events_follow_their_keyframes() {
+        let mut heartbeat = event("Heartbeat", 0.0, 0.0);
+        heartbeat.keyframes = ["0,0.6,0.6,160,320,Step", "0.1,0,0,160,320,Step", "0.3,0.4,0.4,160,320,Step", "0.4,0,0,160,320"]
+            .iter()
+            .map(|keyframe| parse_keyframe(keyframe).ok().unwrap())
+            .collect();
+        heartbeat.time = 0.4;
+
+        let timeline = render_event(&heartbeat, 100);
+
+        assert_eq!(timeline[5].sample.amp_low, 0.6);
+        assert_eq!(timeline[20].sample.amp_low, 0.0);
+        assert_eq!(timeline[35].sample.amp_low, 0.4);
+    }
+
+    #[test]
+    fn chains_are_delayed_and_mixed() {
+        let events: HashMap<String, VibrationEvent> =
+            [event("A", 0.2, 0.25), event("B", 0.2, 0.5)].into_iter().map(|event| (event.name.to_owned(), event)).collect();
+
+        let chain = VibrationEventChain {
+            name: String::from("Combo"),
+            chain: vec![
+                VibrationEventChainItem { name: String::from("A"), delay: 0.0 },
+                VibrationEventChainItem { name: String::from("B"), delay: 0.1 },
+                VibrationEventChainItem { name: String::from("Missing"), delay: 0.0 },
+            ],
+        };
+
+        let max = render_chain(&chain, &events, MixMode::Max, 100);
+        let sum = render_chain(&chain, &events, MixMode::Sum, 100);
+
+        assert_eq!(max.len(), 30);
+        assert_eq!(max[5].sample.amp_low, 0.25);
+        assert_eq!(max[15].sample.amp_low, 0.5);
+        assert_eq!(sum[15].sample.amp_low, 0.75);
+        assert_eq!(max[25].sample.amp_low, 0.5);
+    }
+
+    #[test]
+    fn exports_have_a_row_and_column_per_point() {
+        let timeline = render_event(&event("Hit", 0.1, 0.25), 100);
+
+        let csv = to_csv(&timeline);
+        let png = to_png(&timeline);
+
+        assert_eq!(csv.lines().count(), timeline.len() + 1);
+        assert!(csv.starts_with("time,"));
+        assert_eq!(png.width(), timeline.len() as u32 + PLOT_MARGIN * 2);
+        assert_eq!(png.height(), PLOT_HEIGHT * 2 + PLOT_MARGIN * 3);
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use ordered_float::OrderedFloat;
+
+use super::{parse_keyframe, vibrationevent::parse_event_chain, EasingType, SoundEventMapping, VibrationEventBook, VibrationType};
+
+/// Anything above this is most likely a typo. The strongest bundled events go up to this.
+pub const AMPLITUDE_MAX: f32 = 3.0;
+/// Highest frequency the HD Rumble motors can produce.
+pub const FREQUENCY_MAX: f32 = 1252.0;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A problem found in a [`VibrationEventBook`], along with the row it was found in.
+pub struct VibrationEventIssue {
+    /// Name of the sheet the row is in.
+    pub sheet: String,
+    /// Position of the row in the sheet, starting from 0.
+    pub row: usize,
+    /// `@Name` of the row.
+    pub name: String,
+    pub kind: VibrationEventIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VibrationEventIssueKind {
+    DuplicateName,
+    UnknownEasing(String),
+    UnknownVibrationType(String),
+    OutOfRange { field: &'static str, value: f32, min: f32, max: f32 },
+    NoDuration,
+    InvalidKeyframe(String),
+    KeyframeOutOfOrder { time: f32, previous: f32 },
+    InvalidChainItem(String),
+    DanglingChainReference(String),
+    NegativeDelay { event: String, delay: f32 },
+    InvalidSoundEventMapping(String),
+    DanglingMappingReference(String),
+}
+
+impl fmt::Display for VibrationEventIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} row {} ({}): ", self.sheet, self.row, self.name)?;
+
+        match &self.kind {
+            VibrationEventIssueKind::DuplicateName => write!(f, "this name is already used by another event or chain"),
+            VibrationEventIssueKind::UnknownEasing(value) => write!(f, "unknown easing '{}'", value),
+            VibrationEventIssueKind::UnknownVibrationType(value) => write!(f, "unknown vibration type '{}'", value),
+            VibrationEventIssueKind::OutOfRange { field, value, min, max } => {
+                write!(f, "{} is {}, but should be between {} and {}", field, value, min, max)
+            },
+            VibrationEventIssueKind::NoDuration => write!(f, "Time is missing or not above 0, the event will never be felt"),
+            VibrationEventIssueKind::InvalidKeyframe(error) => write!(f, "{}", error),
+            VibrationEventIssueKind::KeyframeOutOfOrder { time, previous } => {
+                write!(f, "keyframe at {} comes after one at {}, keyframes should be sorted by time", time, previous)
+            },
+            VibrationEventIssueKind::InvalidChainItem(error) => write!(f, "{}", error),
+            VibrationEventIssueKind::DanglingChainReference(event) => write!(f, "chain references '{}', which is not a vibration event", event),
+            VibrationEventIssueKind::NegativeDelay { event, delay } => write!(f, "'{}' is delayed by {}, delays cannot be negative", event, delay),
+            VibrationEventIssueKind::InvalidSoundEventMapping(error) => write!(f, "{}", error),
+            VibrationEventIssueKind::DanglingMappingReference(event) => {
+                write!(f, "mapping runs '{}', which is neither a vibration event nor a chain", event)
+            },
+        }
+    }
+}
+
+impl VibrationEventBook {
+    /// Look for everything in the book that would be ignored or misbehave at runtime.
+    ///
+    /// Nothing is fixed here, the issues are only reported so the author can do something about it.
+    pub fn validate(&self) -> Vec<VibrationEventIssue> {
+        let mut issues = vec![];
+        let mut names = HashSet::new();
+
+        let events_sheet = &self.vibration_events.name;
+        let chains_sheet = &self.vibration_event_chains.name;
+        let mappings_sheet = &self.sound_event_mappings.name;
+
+        let event_names: HashSet<&str> = self.vibration_events.data.iter().map(|entry| entry.name.as_str()).collect();
+
+        self.vibration_events.data.iter().enumerate().for_each(|(row, entry)| {
+            let mut report = |kind| {
+                issues.push(VibrationEventIssue {
+                    sheet: events_sheet.to_owned(),
+                    row,
+                    name: entry.name.to_owned(),
+                    kind,
+                })
+            };
+
+            if !names.insert(entry.name.as_str()) {
+                report(VibrationEventIssueKind::DuplicateName);
+            }
+
+            if let Some(easing) = entry.easing.as_ref().filter(|easing| !easing.is_empty()) {
+                if EasingType::from_str(easing).is_err() {
+                    report(VibrationEventIssueKind::UnknownEasing(easing.to_owned()));
+                }
+            }
+
+            if let Some(vibration_type) = entry.vibration_type.as_ref().filter(|vibration_type| !vibration_type.is_empty()) {
+                if VibrationType::from_str(vibration_type).is_err() {
+                    report(VibrationEventIssueKind::UnknownVibrationType(vibration_type.to_owned()));
+                }
+            }
+
+            let keyframes: Vec<_> = entry.keyframes.iter().filter(|keyframe| !keyframe.trim().is_empty()).collect();
+
+            // Keyframed events last until their last keyframe, @Time is not used
+            if keyframes.is_empty() && entry.time.map_or(true, |time| time.into_inner() <= 0.0) {
+                report(VibrationEventIssueKind::NoDuration);
+            }
+
+            let mut values = vec![
+                ("AmplitudeMagnitude", entry.amplitude_magnitude.map(OrderedFloat::into_inner), AMPLITUDE_MAX),
+                ("AmpLow", entry.amp_low.map(OrderedFloat::into_inner), AMPLITUDE_MAX),
+                ("AmpHigh", entry.amp_high.map(OrderedFloat::into_inner), AMPLITUDE_MAX),
+                ("FreqLow", entry.freq_low.map(OrderedFloat::into_inner), FREQUENCY_MAX),
+                ("FreqHigh", entry.freq_high.map(OrderedFloat::into_inner), FREQUENCY_MAX),
+            ];
+
+            let mut previous_time = 0.0;
+
+            keyframes.into_iter().for_each(|keyframe| match parse_keyframe(keyframe) {
+                Ok(keyframe) => {
+                    if keyframe.time < previous_time {
+                        report(VibrationEventIssueKind::KeyframeOutOfOrder {
+                            time: keyframe.time,
+                            previous: previous_time,
+                        });
+                    }
+
+                    previous_time = keyframe.time;
+
+                    values.extend([
+                        ("Keyframe Time", Some(keyframe.time), f32::MAX),
+                        ("Keyframe AmpLow", Some(keyframe.sample.amp_low), AMPLITUDE_MAX),
+                        ("Keyframe AmpHigh", Some(keyframe.sample.amp_high), AMPLITUDE_MAX),
+                        ("Keyframe FreqLow", Some(keyframe.sample.freq_low), FREQUENCY_MAX),
+                        ("Keyframe FreqHigh", Some(keyframe.sample.freq_high), FREQUENCY_MAX),
+                    ]);
+                },
+                Err(err) => report(VibrationEventIssueKind::InvalidKeyframe(err.to_string())),
+            });
+
+            values.into_iter().for_each(|(field, value, max)| {
+                if let Some(value) = value {
+                    if !(0.0..=max).contains(&value) {
+                        report(VibrationEventIssueKind::OutOfRange { field, value, min: 0.0, max });
+                    }
+                }
+            });
+        });
+
+        self.vibration_event_chains.data.iter().enumerate().for_each(|(row, entry)| {
+            let mut report = |kind| {
+                issues.push(VibrationEventIssue {
+                    sheet: chains_sheet.to_owned(),
+                    row,
+                    name: entry.name.to_owned(),
+                    kind,
+                })
+            };
+
+            // Events are looked up before chains, so a chain sharing a name with an event can never run
+            if !names.insert(entry.name.as_str()) {
+                report(VibrationEventIssueKind::DuplicateName);
+            }
+
+            // A trailing ; leaves an empty item behind, which is harmless
+            entry.chain.iter().filter(|item| !item.trim().is_empty()).for_each(|item| match parse_event_chain(item) {
+                Ok(item) => {
+                    if !event_names.contains(item.name.as_str()) {
+                        report(VibrationEventIssueKind::DanglingChainReference(item.name.to_owned()));
+                    }
+
+                    if item.delay < 0.0 {
+                        report(VibrationEventIssueKind::NegativeDelay {
+                            event: item.name,
+                            delay: item.delay,
+                        });
+                    }
+                },
+                Err(err) => report(VibrationEventIssueKind::InvalidChainItem(err.to_string())),
+            });
+        });
+
+        // Mappings are not looked up by name, so they only have to be unique between themselves
+        let mut mapping_names = HashSet::new();
+
+        self.sound_event_mappings.data.iter().enumerate().for_each(|(row, entry)| {
+            let mut report = |kind| {
+                issues.push(VibrationEventIssue {
+                    sheet: mappings_sheet.to_owned(),
+                    row,
+                    name: entry.name.to_owned(),
+                    kind,
+                })
+            };
+
+            if !mapping_names.insert(entry.name.as_str()) {
+                report(VibrationEventIssueKind::DuplicateName);
+            }
+
+            match SoundEventMapping::try_from(entry) {
+                Ok(mapping) => {
+                    if let Some(event) = mapping.event.filter(|event| !names.contains(event.as_str())) {
+                        report(VibrationEventIssueKind::DanglingMappingReference(event));
+                    }
+                },
+                Err(err) => report(VibrationEventIssueKind::InvalidSoundEventMapping(err.to_string())),
+            }
+        });
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use astra_formats::AstraBook;
+
+    use super::VibrationEventIssueKind;
+    use crate::VibrationEventBook;
+
+    fn book(events: &str, chains: &str) -> VibrationEventBook {
+        book_with_mappings(events, chains, "")
+    }
+
+    fn book_with_mappings(events: &str, chains: &str, mappings: &str) -> VibrationEventBook {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<Book Count="1">
+	<Sheet Name="VibrationEvent" Count="1">
+		<Header>
+			<Param Name="Name" Ident="Name" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Time" Ident="Time" Type="float" Min="" Max="" Chg="" />
+			<Param Name="AmplitudeMagnitude" Ident="AmplitudeMagnitude" Type="float" Min="" Max="" Chg="" />
+			<Param Name="AmpLow" Ident="AmpLow" Type="float" Min="" Max="" Chg="" />
+			<Param Name="AmpHigh" Ident="AmpHigh" Type="float" Min="" Max="" Chg="" />
+			<Param Name="FreqLow" Ident="FreqLow" Type="float" Min="" Max="" Chg="" />
+			<Param Name="FreqHigh" Ident="FreqHigh" Type="float" Min="" Max="" Chg="" />
+			<Param Name="Easing" Ident="Easing" Type="string" Min="" Max="" Chg="" />
+			<Param Name="VibrationType" Ident="VibrationType" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Keyframes" Ident="Keyframes" Type="string[]" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			{events}
+		</Data>
+	</Sheet>
+	<Sheet Name="VibrationEventChains" Count="1">
+		<Header>
+			<Param Name="Name" Ident="Name" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Chain" Ident="Chain" Type="string[]" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			{chains}
+		</Data>
+	</Sheet>
+	<Sheet Name="SoundEventMappings" Count="1">
+		<Header>
+			<Param Name="Name" Ident="Name" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Pattern" Ident="Pattern" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Match" Ident="Match" Type="string" Min="" Max="" Chg="" />
+			<Param Name="EngageOwner" Ident="EngageOwner" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Engaged" Ident="Engaged" Type="string" Min="" Max="" Chg="" />
+			<Param Name="PlayerSide" Ident="PlayerSide" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Critical" Ident="Critical" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Event" Ident="Event" Type="string" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			{mappings}
+		</Data>
+	</Sheet>
+</Book>"#
+        );
+
+        VibrationEventBook::from_string(&xml).unwrap()
+    }
+
+    #[test]
+    fn bundled_events_are_valid() {
+        let book = VibrationEventBook::from_string(include_str!("../../cobalt/resources/VibrationEvent.xml")).unwrap();
+
+        let issues = book.validate();
+
+        assert!(issues.is_empty(), "{}", issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join("\n"));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        let book = book(
+            r#"<Param Name="Bad" Time="0.1" AmplitudeMagnitude="0.1" AmpLow="0.1" AmpHigh="0.1" FreqLow="" FreqHigh="" Easing="EaseInNope" VibrationType="Rumble"/>"#,
+            "",
+        );
+
+        let kinds: Vec<_> = book.validate().into_iter().map(|issue| issue.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                VibrationEventIssueKind::UnknownEasing(String::from("EaseInNope")),
+                VibrationEventIssueKind::UnknownVibrationType(String::from("Rumble")),
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_values_are_reported() {
+        let book = book(
+            r#"<Param Name="Loud" Time="0" AmplitudeMagnitude="0.1" AmpLow="-0.5" AmpHigh="4.0" FreqLow="160" FreqHigh="5000" Easing="" VibrationType="UI"/>"#,
+            "",
+        );
+
+        let issues = book.validate();
+
+        assert_eq!(issues.len(), 4);
+        assert!(issues.iter().all(|issue| issue.name == "Loud" && issue.row == 0));
+        assert_eq!(issues[0].kind, VibrationEventIssueKind::NoDuration);
+        assert!(matches!(issues[1].kind, VibrationEventIssueKind::OutOfRange { field: "AmpLow", .. }));
+        assert!(matches!(issues[2].kind, VibrationEventIssueKind::OutOfRange { field: "AmpHigh", .. }));
+        assert!(matches!(issues[3].kind, VibrationEventIssueKind::OutOfRange { field: "FreqHigh", .. }));
+    }
+
+    #[test]
+    fn keyframe_problems_are_reported() {
+        let book = book(
+            r#"<Param Name="Heartbeat" Time="" AmplitudeMagnitude="0.1" AmpLow="" AmpHigh="" FreqLow="" FreqHigh="" Easing="" VibrationType="Combat" Keyframes="0,0.5,0.5,160,320,Step;0.1,0,0,160,320;0.05,2000,0,160,320;0.2,0,0,160,320,Wobbly;"/>"#,
+            "",
+        );
+
+        let kinds: Vec<_> = book.validate().into_iter().map(|issue| issue.kind).collect();
+
+        assert_eq!(kinds.len(), 3);
+        assert_eq!(kinds[0], VibrationEventIssueKind::KeyframeOutOfOrder { time: 0.05, previous: 0.1 });
+        assert!(matches!(kinds[1], VibrationEventIssueKind::InvalidKeyframe(_)));
+        assert!(matches!(kinds[2], VibrationEventIssueKind::OutOfRange { field: "Keyframe AmpLow", .. }));
+    }
+
+    #[test]
+    fn chain_problems_are_reported() {
+        let book = book(
+            r#"<Param Name="Hit" Time="0.1" AmplitudeMagnitude="0.1" AmpLow="0.1" AmpHigh="0.1" FreqLow="" FreqHigh="" Easing="" VibrationType="Combat"/>"#,
+            r#"<Param Name="Combo" Chain="Hit,0;Hit,-0.5;Missing,0.2;Hit;"/>
+			<Param Name="Hit" Chain="Hit,0;"/>"#,
+        );
+
+        let issues = book.validate();
+
+        assert_eq!(issues.len(), 4);
+        assert_eq!(issues[0].sheet, "VibrationEventChains");
+        assert_eq!(
+            issues[0].kind,
+            VibrationEventIssueKind::NegativeDelay {
+                event: String::from("Hit"),
+                delay: -0.5
+            }
+        );
+        assert_eq!(issues[1].kind, VibrationEventIssueKind::DanglingChainReference(String::from("Missing")));
+        assert!(matches!(issues[2].kind, VibrationEventIssueKind::InvalidChainItem(_)));
+        assert_eq!((issues[3].row, &issues[3].kind), (1, &VibrationEventIssueKind::DuplicateName));
+    }
+
+    #[test]
+    fn mapping_problems_are_reported() {
+        let book = book_with_mappings(
+            r#"<Param Name="Hit" Time="0.1" AmplitudeMagnitude="0.1" AmpLow="0.1" AmpHigh="0.1" FreqLow="" FreqHigh="" Easing="" VibrationType="Combat"/>"#,
+            r#"<Param Name="Combo" Chain="Hit,0;"/>"#,
+            r#"<Param Name="ToEvent" Pattern="SE_Hit" Match="" EngageOwner="" Engaged="" PlayerSide="" Critical="" Event="Hit"/>
+			<Param Name="ToChain" Pattern="SE_Combo_" Match="Prefix" EngageOwner="" Engaged="" PlayerSide="true" Critical="" Event="Combo"/>
+			<Param Name="Silence" Pattern="SE_.*_Loop" Match="Regex" EngageOwner="" Engaged="" PlayerSide="" Critical="" Event=""/>
+			<Param Name="Missing" Pattern="SE_Miss" Match="" EngageOwner="" Engaged="" PlayerSide="" Critical="" Event="Miss"/>
+			<Param Name="BadRegex" Pattern="SE_(" Match="Regex" EngageOwner="" Engaged="" PlayerSide="" Critical="" Event="Hit"/>
+			<Param Name="ToEvent" Pattern="SE_Hit" Match="" EngageOwner="maybe" Engaged="" PlayerSide="" Critical="" Event="Hit"/>"#,
+        );
+
+        let issues = book.validate();
+
+        assert_eq!(issues.len(), 4);
+        assert!(issues.iter().all(|issue| issue.sheet == "SoundEventMappings"));
+        assert_eq!((issues[0].row, &issues[0].kind), (3, &VibrationEventIssueKind::DanglingMappingReference(String::from("Miss"))));
+        assert!(matches!(issues[1].kind, VibrationEventIssueKind::InvalidSoundEventMapping(_)));
+        assert_eq!((issues[2].row, &issues[2].kind), (5, &VibrationEventIssueKind::DuplicateName));
+        assert!(matches!(issues[3].kind, VibrationEventIssueKind::InvalidSoundEventMapping(_)));
+    }
+}
@@ -0,0 +1,295 @@
+use std::io::Read;
+
+use crate::{hash, parse_wem_markers, reader::{ChunkStream, Reader}, Marker, WwiseError};
+
+/// HIRC object types we care about. Everything else (containers, buses, ...) is skipped.
+const HIRC_SOUND: u8 = 2;
+const HIRC_EVENT: u8 = 4;
+
+/// Banks up to this version store the action count of events as a `u32`, later ones use a variable-length integer.
+const LAST_FIXED_ACTION_COUNT_VERSION: u32 = 122;
+/// Banks up to this version store the stream type of sounds as a `u32`, later ones use a `u8`.
+const LAST_WIDE_STREAM_TYPE_VERSION: u32 = 88;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BankEvent {
+    /// Hash of the event name.
+    pub id: u32,
+    /// IDs of the actions the event performs, in order.
+    pub actions: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BankSound {
+    pub id: u32,
+    /// ID of the WEM the sound plays, embedded in the bank or streamed from a loose file.
+    pub media_id: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A WEM embedded in the bank, as listed in the DIDX section.
+pub struct MediaEntry {
+    pub id: u32,
+    /// Offset from the start of the DATA section.
+    pub offset: u32,
+    pub size: u32,
+    /// Markers found in the WEM, in the order they appear.
+    pub markers: Vec<Marker>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundBank {
+    /// Version of the bank format, which depends on the version of Wwise that generated it.
+    pub version: u32,
+    /// Hash of the bank name. Wwise refuses to load two banks with the same ID.
+    pub id: u32,
+    pub language_id: u32,
+    pub events: Vec<BankEvent>,
+    pub sounds: Vec<BankSound>,
+    pub media: Vec<MediaEntry>,
+}
+
+impl SoundBank {
+    /// Parse the sections of a `.bnk` file we know about (BKHD, DIDX, DATA and HIRC) and skip the others.
+    pub fn parse(data: &[u8]) -> Result<Self, WwiseError> {
+        let mut reader = Reader::new(data);
+
+        let (tag, header) = reader.chunk().map_err(|_| WwiseError::MissingHeader)?;
+
+        if &tag != b"BKHD" {
+            return Err(WwiseError::MissingHeader);
+        }
+
+        let mut bank = Self::from_header(header)?;
+
+        let mut media_data = None;
+
+        while !reader.is_empty() {
+            let (tag, section) = reader.chunk()?;
+
+            match &tag {
+                b"DIDX" => bank.media = parse_didx(section)?,
+                b"DATA" => media_data = Some(section),
+                b"HIRC" => parse_hirc(&mut bank, section)?,
+                _ => (),
+            }
+        }
+
+        if let Some(media_data) = media_data {
+            for media in bank.media.iter_mut() {
+                let start = media.offset as usize;
+                let end = start + media.size as usize;
+
+                let wem = media_data.get(start..end).ok_or_else(|| WwiseError::MalformedSection {
+                    section: String::from("DIDX"),
+                    reason: format!("media {} ends at {:#x}, past the end of the DATA section ({:#x})", media.id, end, media_data.len()),
+                })?;
+
+                media.markers = parse_wem_markers(wem);
+            }
+        }
+
+        Ok(bank)
+    }
+
+    /// Parse only the BKHD and HIRC sections of a `.bnk` file as it is being read, without keeping the embedded media in memory.
+    ///
+    /// This is enough to know the events and sounds of a bank, but [`SoundBank::media`] stays empty so no markers are found.
+    pub fn read_hierarchy(reader: impl Read) -> Result<Self, WwiseError> {
+        let mut chunks = ChunkStream::new(reader);
+
+        let mut bank = match chunks.next(|_| true) {
+            Ok(Some((tag, Some(header)))) if &tag == b"BKHD" => Self::from_header(&header)?,
+            Err(WwiseError::Io(err)) => return Err(WwiseError::Io(err)),
+            _ => return Err(WwiseError::MissingHeader),
+        };
+
+        while let Some((_, section)) = chunks.next(|tag| tag == b"HIRC")? {
+            if let Some(section) = section {
+                parse_hirc(&mut bank, &section)?;
+            }
+        }
+
+        Ok(bank)
+    }
+
+    fn from_header(header: &[u8]) -> Result<Self, WwiseError> {
+        let mut header = Reader::new(header);
+
+        Ok(SoundBank {
+            version: header.u32()?,
+            id: header.u32()?,
+            language_id: header.u32()?,
+            events: vec![],
+            sounds: vec![],
+            media: vec![],
+        })
+    }
+
+    /// Look for an event by name.
+    pub fn event(&self, name: &str) -> Option<&BankEvent> {
+        let id = hash(name);
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    /// Every marker of the embedded media, along with the ID of the media it was found in.
+    pub fn markers(&self) -> impl Iterator<Item = (u32, &Marker)> {
+        self.media.iter().flat_map(|media| media.markers.iter().map(move |marker| (media.id, marker)))
+    }
+}
+
+fn parse_didx(section: &[u8]) -> Result<Vec<MediaEntry>, WwiseError> {
+    let count = section.len() / 12;
+
+    if count * 12 != section.len() {
+        return Err(WwiseError::MalformedSection {
+            section: String::from("DIDX"),
+            reason: format!("size {} is not a multiple of 12", section.len()),
+        });
+    }
+
+    let mut reader = Reader::new(section);
+    let mut media = Vec::with_capacity(count);
+
+    while !reader.is_empty() {
+        media.push(MediaEntry {
+            id: reader.u32()?,
+            offset: reader.u32()?,
+            size: reader.u32()?,
+            markers: vec![],
+        });
+    }
+
+    Ok(media)
+}
+
+fn parse_hirc(bank: &mut SoundBank, section: &[u8]) -> Result<(), WwiseError> {
+    let mut reader = Reader::new(section);
+    let count = reader.u32()?;
+
+    for _ in 0..count {
+        let object_type = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let mut object = Reader::new(reader.take(size)?);
+        let id = object.u32()?;
+
+        match object_type {
+            HIRC_EVENT => {
+                let action_count = if bank.version <= LAST_FIXED_ACTION_COUNT_VERSION {
+                    object.u32()?
+                } else {
+                    object.var()?
+                };
+
+                let actions = (0..action_count).map(|_| object.u32()).collect::<Result<_, _>>()?;
+
+                bank.events.push(BankEvent { id, actions });
+            },
+            HIRC_SOUND => {
+                // Plugin ID, then the stream type
+                object.skip(4)?;
+                object.skip(if bank.version <= LAST_WIDE_STREAM_TYPE_VERSION { 4 } else { 1 })?;
+
+                bank.sounds.push(BankSound { id, media_id: object.u32()? });
+            },
+            _ => (),
+        }
+    }
+
+    if reader.position() != section.len() {
+        return Err(WwiseError::MalformedSection {
+            section: String::from("HIRC"),
+            reason: format!("{} objects were read, but {} bytes are left over", count, section.len() - reader.position()),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::SoundBank;
+    use crate::{hash, wem::tests::wem_with_markers, WwiseError};
+
+    fn chunk(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        [tag.as_slice(), &(data.len() as u32).to_le_bytes(), data].concat()
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_le_bytes()).collect()
+    }
+
+    fn hirc_object(object_type: u8, body: &[u8]) -> Vec<u8> {
+        [&[object_type][..], &(body.len() as u32).to_le_bytes(), body].concat()
+    }
+
+    /// Build a bank with the given events, a sound playing media 1000 and that media embedded with markers.
+    pub(crate) fn bank(version: u32, name: &str, events: &[&str]) -> Vec<u8> {
+        let wem = wem_with_markers(&[(1, 4800, "CobaltEvent:V_Pick_Original")]);
+
+        let mut objects = vec![hirc_object(2, &[u32s(&[500, 0x40001]), vec![0], u32s(&[1000, wem.len() as u32]), vec![0]].concat())];
+
+        objects.extend(events.iter().map(|event| {
+            let count = if version <= 122 { u32s(&[2]) } else { vec![2] };
+            hirc_object(4, &[u32s(&[hash(event)]), count, u32s(&[10, 11])].concat())
+        }));
+
+        let hirc = [u32s(&[objects.len() as u32]), objects.concat()].concat();
+
+        [
+            chunk(b"BKHD", &u32s(&[version, hash(name), 0, 0])),
+            chunk(b"DIDX", &u32s(&[1000, 0, wem.len() as u32])),
+            chunk(b"DATA", &wem),
+            chunk(b"HIRC", &hirc),
+            chunk(b"STID", &[0; 8]),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn sections_are_parsed() {
+        let bank = SoundBank::parse(&bank(134, "SeasideDragon", &["V_Pick_SeasideDragon", "V_Critical_SeasideDragon"])).unwrap();
+
+        assert_eq!(bank.version, 134);
+        assert_eq!(bank.id, hash("SeasideDragon"));
+        assert_eq!(bank.events.len(), 2);
+        assert_eq!(bank.event("V_Pick_SeasideDragon").unwrap().actions, vec![10, 11]);
+        assert!(bank.event("V_Pick_PlayerF").is_none());
+        assert_eq!((bank.sounds[0].id, bank.sounds[0].media_id), (500, 1000));
+        assert_eq!((bank.media[0].id, bank.media[0].offset), (1000, 0));
+
+        let markers: Vec<_> = bank.markers().map(|(media, marker)| (media, marker.label.as_str())).collect();
+        assert_eq!(markers, vec![(1000, "CobaltEvent:V_Pick_Original")]);
+    }
+
+    #[test]
+    fn older_banks_use_fixed_action_counts() {
+        let bank = SoundBank::parse(&bank(113, "Old", &["V_Pick_Old"])).unwrap();
+
+        assert_eq!(bank.event("V_Pick_Old").unwrap().actions, vec![10, 11]);
+    }
+
+    #[test]
+    fn hierarchy_is_read_without_the_media() {
+        let data = bank(134, "SeasideDragon", &["V_Pick_SeasideDragon", "V_Critical_SeasideDragon"]);
+        let parsed = SoundBank::parse(&data).unwrap();
+
+        let bank = SoundBank::read_hierarchy(data.as_slice()).unwrap();
+
+        assert_eq!((bank.version, bank.id), (parsed.version, parsed.id));
+        assert_eq!(bank.events, parsed.events);
+        assert_eq!(bank.sounds, parsed.sounds);
+        assert!(bank.media.is_empty());
+
+        assert_eq!(SoundBank::read_hierarchy(b"RIFF".as_slice()).unwrap_err(), WwiseError::MissingHeader);
+        assert!(matches!(SoundBank::read_hierarchy(&data[..data.len() - 20]), Err(WwiseError::UnexpectedEof { .. })));
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        let data = bank(134, "Broken", &["V_Pick_Broken"]);
+
+        assert_eq!(SoundBank::parse(b"RIFF").unwrap_err(), WwiseError::MissingHeader);
+        assert!(matches!(SoundBank::parse(&data[..data.len() - 20]), Err(WwiseError::UnexpectedEof { .. })));
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{hash, SoundBank};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollisionKind {
+    /// Two banks have the same ID, so Wwise only loads one of them.
+    BankId(u32),
+    /// Two banks define the same event, so only one of them is played.
+    EventId(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Something one bank provides that is also provided by another one.
+pub struct Collision {
+    pub kind: CollisionKind,
+    /// Vanilla bank name or path of the modded bank that provided it first.
+    pub first: String,
+    /// Path of the modded bank that provides it again.
+    pub second: String,
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            CollisionKind::BankId(id) => write!(f, "'{}' has the same bank ID ({}) as '{}', only one of them will load", self.second, id, self.first),
+            CollisionKind::EventId(id) => write!(f, "'{}' defines event {} again, which '{}' already does", self.second, id, self.first),
+        }
+    }
+}
+
+/// Look for IDs provided by more than one bank.
+///
+/// `vanilla_banks` are the names of the banks the game loads, with their content when it could be read. A modded bank whose file is
+/// named after one of them replaces it, so it is only reported if another modded bank ends up with the same ID, and the events of the
+/// vanilla bank no longer count.
+/// `mod_banks` are the path of each modded bank along with its content, in the order they are loaded.
+pub fn find_collisions(vanilla_banks: &[(String, Option<SoundBank>)], mod_banks: &[(String, SoundBank)]) -> Vec<Collision> {
+    let mut collisions = vec![];
+
+    let mut bank_ids: HashMap<u32, String> = vanilla_banks.iter().map(|(name, _)| (hash(name), name.to_owned())).collect();
+
+    let mut event_ids: HashMap<u32, &str> = vanilla_banks
+        .iter()
+        .filter(|(name, _)| !mod_banks.iter().any(|(path, _)| is_named(path, name)))
+        .filter_map(|(name, bank)| bank.as_ref().map(|bank| (name, bank)))
+        .flat_map(|(name, bank)| bank.events.iter().map(move |event| (event.id, name.as_str())))
+        .collect();
+
+    for (path, bank) in mod_banks {
+        match bank_ids.get(&bank.id) {
+            // Replacing a vanilla bank on purpose
+            Some(vanilla) if is_named(path, vanilla) => {
+                bank_ids.insert(bank.id, path.to_owned());
+            },
+            Some(first) => collisions.push(Collision {
+                kind: CollisionKind::BankId(bank.id),
+                first: first.to_owned(),
+                second: path.to_owned(),
+            }),
+            None => {
+                bank_ids.insert(bank.id, path.to_owned());
+            },
+        }
+
+        for event in bank.events.iter() {
+            match event_ids.get(&event.id) {
+                Some(first) if first != path => collisions.push(Collision {
+                    kind: CollisionKind::EventId(event.id),
+                    first: first.to_string(),
+                    second: path.to_owned(),
+                }),
+                _ => {
+                    event_ids.insert(event.id, path);
+                },
+            }
+        }
+    }
+
+    collisions
+}
+
+/// Check if the file at `path` is named like the vanilla bank, ignoring the directories and extension.
+fn is_named(path: &str, name: &str) -> bool {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+
+    stem.eq_ignore_ascii_case(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_collisions, CollisionKind};
+    use crate::{bank::tests::bank, hash, SoundBank};
+
+    fn modded(path: &str, name: &str, events: &[&str]) -> (String, SoundBank) {
+        (path.to_owned(), SoundBank::parse(&bank(134, name, events)).unwrap())
+    }
+
+    fn vanilla(name: &str, events: &[&str]) -> (String, Option<SoundBank>) {
+        (name.to_owned(), Some(SoundBank::parse(&bank(134, name, events)).unwrap()))
+    }
+
+    #[test]
+    fn replacing_vanilla_banks_is_fine() {
+        let banks = [modded("Audio/GeneratedSoundBanks/Switch/Voice_PlayerF.bnk", "Voice_PlayerF", &["V_Pick_PlayerF"])];
+
+        let vanilla_banks = [vanilla("Voice_PlayerF", &["V_Pick_PlayerF"]), (String::from("Init"), None)];
+
+        assert!(find_collisions(&vanilla_banks, &banks).is_empty());
+    }
+
+    #[test]
+    fn renamed_copies_of_vanilla_banks_are_reported() {
+        let banks = [modded("Audio/GeneratedSoundBanks/Switch/MyVoice.bnk", "Voice_PlayerF", &[])];
+
+        let collisions = find_collisions(&[vanilla("Voice_PlayerF", &[])], &banks);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].kind, CollisionKind::BankId(hash("Voice_PlayerF")));
+        assert_eq!(collisions[0].first, "Voice_PlayerF");
+    }
+
+    #[test]
+    fn mods_colliding_with_each_other_are_reported() {
+        let banks = [
+            modded("A/SeasideDragon.bnk", "SeasideDragon", &["V_Pick_SeasideDragon", "V_Pick_Costume"]),
+            modded("B/SeasideDragon2.bnk", "SeasideDragon", &["V_Critical_SeasideDragon"]),
+            modded("C/Costume.bnk", "Costume", &["V_Pick_Costume"]),
+        ];
+
+        let collisions = find_collisions(&[], &banks);
+
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(collisions[0].kind, CollisionKind::BankId(hash("SeasideDragon")));
+        assert_eq!((collisions[0].first.as_str(), collisions[0].second.as_str()), ("A/SeasideDragon.bnk", "B/SeasideDragon2.bnk"));
+        assert_eq!(collisions[1].kind, CollisionKind::EventId(hash("V_Pick_Costume")));
+        assert_eq!(collisions[1].second, "C/Costume.bnk");
+    }
+
+    #[test]
+    fn events_colliding_with_vanilla_are_reported() {
+        let banks = [modded("A/SeasideDragon.bnk", "SeasideDragon", &["V_Pick_SeasideDragon", "V_Pick_PlayerF"])];
+
+        let collisions = find_collisions(&[vanilla("Voice_PlayerF", &["V_Pick_PlayerF"])], &banks);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].kind, CollisionKind::EventId(hash("V_Pick_PlayerF")));
+        assert_eq!((collisions[0].first.as_str(), collisions[0].second.as_str()), ("Voice_PlayerF", "A/SeasideDragon.bnk"));
+    }
+}
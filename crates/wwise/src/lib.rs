@@ -0,0 +1,45 @@
+//! Read what's inside Wwise soundbanks, without needing the game or Wwise to be running.
+//!
+//! Banks don't store the names of their events, only their FNV-1 hashes. Use [`hash`] to turn a name into the ID Wwise uses for it.
+
+mod bank;
+mod collision;
+mod reader;
+mod wem;
+
+pub use bank::*;
+pub use collision::*;
+pub use wem::*;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum WwiseError {
+    #[error("expected {expected} more bytes at offset {offset:#x}, but the file ends before that")]
+    UnexpectedEof { offset: usize, expected: usize },
+    #[error("the file does not start with a BKHD section, it is probably not a soundbank")]
+    MissingHeader,
+    #[error("the {section} section is malformed: {reason}")]
+    MalformedSection { section: String, reason: String },
+    #[error("the file could not be read: {0}")]
+    Io(String),
+}
+
+/// Hash a name like Wwise does for bank, event, switch and state IDs (32-bit FNV-1 of the lowercase name).
+pub fn hash(name: &str) -> u32 {
+    name.to_lowercase()
+        .bytes()
+        .fold(2166136261u32, |hash, byte| hash.wrapping_mul(16777619) ^ byte as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hash;
+
+    #[test]
+    fn names_are_hashed_like_wwise() {
+        // ID of the Init bank every Wwise project has
+        assert_eq!(hash("Init"), 1355168291);
+        assert_eq!(hash("V_Pick_PlayerF"), hash("v_pick_playerf"));
+    }
+}
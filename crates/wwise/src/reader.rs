@@ -0,0 +1,153 @@
+use std::io::Read;
+
+use crate::WwiseError;
+
+/// Little-endian cursor over a byte slice that reports where it ran out of data.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    pub fn take(&mut self, count: usize) -> Result<&'a [u8], WwiseError> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.data.len()).ok_or(WwiseError::UnexpectedEof {
+            offset: self.position,
+            expected: count,
+        })?;
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, count: usize) -> Result<(), WwiseError> {
+        self.take(count).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, WwiseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, WwiseError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn tag(&mut self) -> Result<[u8; 4], WwiseError> {
+        Ok(self.take(4)?.try_into().unwrap())
+    }
+
+    /// Variable-length integer used by recent banks, 7 bits per byte with the most significant byte first.
+    pub fn var(&mut self) -> Result<u32, WwiseError> {
+        let mut value = 0u32;
+
+        loop {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Read a `<tag><u32 size><data>` chunk, as used by both banks and RIFF files.
+    pub fn chunk(&mut self) -> Result<([u8; 4], &'a [u8]), WwiseError> {
+        let tag = self.tag()?;
+        let size = self.u32()? as usize;
+
+        Ok((tag, self.take(size)?))
+    }
+}
+
+/// Tag of a chunk, with its data when it was kept.
+pub(crate) type StreamedChunk = ([u8; 4], Option<Vec<u8>>);
+
+/// Reads `<tag><u32 size><data>` chunks one at a time from a stream, only keeping the ones that are asked for.
+pub(crate) struct ChunkStream<R> {
+    reader: R,
+    position: usize,
+}
+
+impl<R: Read> ChunkStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, position: 0 }
+    }
+
+    /// Read the next chunk, with its data if `keep` wants it. Chunks that are not kept are skipped without being stored.
+    ///
+    /// Returns `None` once the stream ends between two chunks.
+    pub fn next(&mut self, keep: impl Fn(&[u8; 4]) -> bool) -> Result<Option<StreamedChunk>, WwiseError> {
+        let mut header = [0; 8];
+
+        match self.fill(&mut header)? {
+            0 => return Ok(None),
+            8 => (),
+            read => {
+                return Err(WwiseError::UnexpectedEof {
+                    offset: self.position,
+                    expected: 8 - read,
+                })
+            },
+        }
+
+        let tag: [u8; 4] = header[..4].try_into().unwrap();
+        let size = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+
+        if keep(&tag) {
+            let mut data = vec![0; size];
+            let read = self.fill(&mut data)?;
+
+            if read != size {
+                return Err(WwiseError::UnexpectedEof {
+                    offset: self.position,
+                    expected: size - read,
+                });
+            }
+
+            Ok(Some((tag, Some(data))))
+        } else {
+            let skipped = std::io::copy(&mut self.reader.by_ref().take(size as u64), &mut std::io::sink()).map_err(|err| WwiseError::Io(err.to_string()))?;
+            self.position += skipped as usize;
+
+            if skipped as usize != size {
+                return Err(WwiseError::UnexpectedEof {
+                    offset: self.position,
+                    expected: size - skipped as usize,
+                });
+            }
+
+            Ok(Some((tag, None)))
+        }
+    }
+
+    /// Read until `buf` is full or the stream ends, returning how many bytes were read.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<usize, WwiseError> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(count) => read += count,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(WwiseError::Io(err.to_string())),
+            }
+        }
+
+        self.position += read;
+
+        Ok(read)
+    }
+}
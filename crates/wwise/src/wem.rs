@@ -0,0 +1,144 @@
+use crate::reader::Reader;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A marker placed in a WEM, which Wwise reports through marker callbacks while the sound plays.
+pub struct Marker {
+    pub id: u32,
+    /// Position in samples from the start of the sound.
+    pub position: u32,
+    /// Empty if the marker has no label.
+    pub label: String,
+}
+
+/// Read the markers of a WEM from its `cue ` and `LIST`/`adtl` chunks.
+///
+/// WEMs are RIFF files, so this works on both the media embedded in banks and loose `.wem` files.
+/// Anything that doesn't look like a RIFF file simply has no markers.
+pub fn parse_wem_markers(data: &[u8]) -> Vec<Marker> {
+    let mut reader = Reader::new(data);
+
+    match (reader.tag(), reader.u32(), reader.tag()) {
+        (Ok(riff), Ok(_), Ok(_)) if &riff == b"RIFF" => (),
+        _ => return vec![],
+    }
+
+    let mut markers = vec![];
+    let mut labels = vec![];
+
+    while let Ok((tag, chunk)) = reader.chunk() {
+        match &tag {
+            b"cue " => markers = parse_cue(chunk),
+            b"LIST" if chunk.starts_with(b"adtl") => labels = parse_labels(&chunk[4..]),
+            _ => (),
+        }
+
+        // RIFF chunks are padded to an even size
+        if chunk.len() % 2 == 1 && reader.skip(1).is_err() {
+            break;
+        }
+    }
+
+    markers.iter_mut().for_each(|marker| {
+        if let Some((_, label)) = labels.iter().find(|(id, _)| *id == marker.id) {
+            marker.label = label.to_owned();
+        }
+    });
+
+    markers
+}
+
+fn parse_cue(chunk: &[u8]) -> Vec<Marker> {
+    let mut reader = Reader::new(chunk);
+    let count = reader.u32().unwrap_or_default();
+
+    (0..count)
+        .map_while(|_| {
+            let id = reader.u32().ok()?;
+            let position = reader.u32().ok()?;
+            // Chunk ID, chunk start, block start and sample offset, which we don't need
+            reader.skip(16).ok()?;
+
+            Some(Marker {
+                id,
+                position,
+                label: String::new(),
+            })
+        })
+        .collect()
+}
+
+fn parse_labels(chunk: &[u8]) -> Vec<(u32, String)> {
+    let mut reader = Reader::new(chunk);
+    let mut labels = vec![];
+
+    while let Ok((tag, subchunk)) = reader.chunk() {
+        if &tag == b"labl" && subchunk.len() >= 4 {
+            let id = u32::from_le_bytes(subchunk[..4].try_into().unwrap());
+            let text = subchunk[4..].split(|byte| *byte == 0).next().unwrap_or_default();
+
+            labels.push((id, String::from_utf8_lossy(text).into_owned()));
+        }
+
+        if subchunk.len() % 2 == 1 && reader.skip(1).is_err() {
+            break;
+        }
+    }
+
+    labels
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::parse_wem_markers;
+
+    fn chunk(tag: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = [tag.as_slice(), &(data.len() as u32).to_le_bytes(), data].concat();
+
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+
+        chunk
+    }
+
+    /// Build a WEM with a dummy format chunk and the given `(id, position, label)` markers.
+    pub(crate) fn wem_with_markers(markers: &[(u32, u32, &str)]) -> Vec<u8> {
+        let cue: Vec<u8> = markers
+            .iter()
+            .flat_map(|(id, position, _)| [*id, *position, u32::from_le_bytes(*b"data"), 0, 0, *position])
+            .flat_map(u32::to_le_bytes)
+            .collect();
+
+        let labels: Vec<u8> = markers
+            .iter()
+            .flat_map(|(id, _, label)| chunk(b"labl", &[id.to_le_bytes().as_slice(), label.as_bytes(), &[0]].concat()))
+            .collect();
+
+        let body = [
+            b"WAVE".to_vec(),
+            chunk(b"fmt ", &[0; 24]),
+            chunk(b"cue ", &[(markers.len() as u32).to_le_bytes().to_vec(), cue].concat()),
+            chunk(b"LIST", &[b"adtl".to_vec(), labels].concat()),
+            chunk(b"data", &[0; 7]),
+        ]
+        .concat();
+
+        [b"RIFF".to_vec(), (body.len() as u32).to_le_bytes().to_vec(), body].concat()
+    }
+
+    #[test]
+    fn markers_get_their_labels() {
+        let markers = parse_wem_markers(&wem_with_markers(&[(1, 0, "CobaltEvent:V_Critical_Original"), (2, 48000, "Odd")]));
+
+        assert_eq!(markers.len(), 2);
+        assert_eq!((markers[0].id, markers[0].position, markers[0].label.as_str()), (1, 0, "CobaltEvent:V_Critical_Original"));
+        assert_eq!((markers[1].id, markers[1].position, markers[1].label.as_str()), (2, 48000, "Odd"));
+    }
+
+    #[test]
+    fn anything_else_has_no_markers() {
+        assert!(parse_wem_markers(&wem_with_markers(&[])).is_empty());
+        assert!(parse_wem_markers(b"BKHD").is_empty());
+        assert!(parse_wem_markers(&[]).is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Detection of rows that patches from several mods change, since only the last patch applied to a row is kept.
+//!
+//! Rows are told apart by their first column (`Iid`, `Pid`, ...), as listed in the header of the sheet in the base XML.
+
+use std::{collections::HashMap, fmt};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::convert::{read_attributes, read_layouts, ConvertError};
+
+type Row = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RowKey {
+    pub sheet: String,
+    pub key: String,
+}
+
+/// The rows of the base XML by sheet and key, to tell which rows a patch actually changes.
+pub struct BaseRows {
+    /// Name of the key column of every sheet, with the rows of the sheet by key.
+    sheets: HashMap<String, (String, HashMap<String, Row>)>,
+}
+
+impl BaseRows {
+    pub fn new(base: &str) -> Result<Self, ConvertError> {
+        let mut sheets: HashMap<String, (String, HashMap<String, Row>)> = read_layouts(base)?
+            .into_iter()
+            .filter_map(|layout| {
+                let key_column = layout.columns().next()?.to_owned();
+                Some((layout.name().to_owned(), (key_column, HashMap::new())))
+            })
+            .collect();
+
+        for (sheet, rows) in read_rows(base)? {
+            if let Some((key_column, keyed)) = sheets.get_mut(&sheet) {
+                for row in rows {
+                    if let Some(key) = row_key(&row, key_column) {
+                        keyed.insert(key.to_owned(), row);
+                    }
+                }
+            }
+        }
+
+        Ok(Self { sheets })
+    }
+
+    /// Find the rows a patch adds or changes the value of at least one column of. Rows identical to the base are left alone.
+    pub fn changed_rows(&self, patch: &str) -> Result<Vec<RowKey>, ConvertError> {
+        let mut changed = vec![];
+
+        for (sheet, rows) in read_rows(patch)? {
+            let (key_column, base_rows) = self.sheets.get(&sheet).ok_or_else(|| ConvertError::UnknownSheet(sheet.to_owned()))?;
+
+            for row in rows {
+                let Some(key) = row_key(&row, key_column) else {
+                    continue;
+                };
+
+                let is_changed = match base_rows.get(key) {
+                    Some(base_row) => row.iter().any(|attribute| !base_row.contains(attribute)),
+                    None => true,
+                };
+
+                if is_changed {
+                    changed.push(RowKey { sheet: sheet.to_owned(), key: key.to_owned() });
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// The rows a patch changes, and where it comes from.
+pub struct PatchRows<'a> {
+    /// Full path of the patch.
+    pub source: &'a str,
+    /// Root of the mod providing the patch, since a mod can have a patch in every format.
+    pub mod_root: &'a str,
+    /// Whether the patch was converted from CSV or YAML.
+    pub converted: bool,
+    pub rows: Vec<RowKey>,
+}
+
+/// A row that patches from several mods change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowConflict {
+    pub row: RowKey,
+    /// Patches changing the row, in the order they are applied.
+    pub sources: Vec<String>,
+}
+
+impl fmt::Display for RowConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "row `{}` of sheet `{}` is changed by {}, only the changes of {} are kept",
+            self.row.key,
+            self.row.sheet,
+            self.sources.join(", "),
+            self.sources.last().map(String::as_str).unwrap_or_default()
+        )
+    }
+}
+
+/// Find the rows that a CSV or YAML patch changes and a patch from another mod changes too.
+///
+/// `patches` are expected in the order they are applied, and conflicts are returned in the order their rows first show up.
+pub fn find_conflicts(patches: &[PatchRows]) -> Vec<RowConflict> {
+    let mut rows: Vec<(&RowKey, Vec<&PatchRows>)> = vec![];
+    let mut indices: HashMap<&RowKey, usize> = HashMap::new();
+
+    for patch in patches {
+        for row in patch.rows.iter() {
+            match indices.get(row) {
+                Some(idx) => rows[*idx].1.push(patch),
+                None => {
+                    indices.insert(row, rows.len());
+                    rows.push((row, vec![patch]));
+                },
+            }
+        }
+    }
+
+    rows.into_iter()
+        .filter(|(_, patches)| {
+            patches.iter().any(|patch| patch.converted) && patches.iter().any(|patch| patch.mod_root != patches[0].mod_root)
+        })
+        .map(|(row, patches)| RowConflict {
+            row: row.clone(),
+            sources: patches.iter().map(|patch| patch.source.to_owned()).collect(),
+        })
+        .collect()
+}
+
+fn row_key<'a>(row: &'a Row, key_column: &str) -> Option<&'a str> {
+    row.iter().find(|(column, _)| column == key_column).map(|(_, value)| value.as_str()).filter(|key| !key.is_empty())
+}
+
+/// Read the rows in the `<Data>` of every sheet of a book.
+fn read_rows(xml: &str) -> Result<Vec<(String, Vec<Row>)>, ConvertError> {
+    let mut reader = Reader::from_str(xml.trim_start_matches(|c| c != '<'));
+    reader.trim_text(true);
+
+    let mut sheets: Vec<(String, Vec<Row>)> = vec![];
+    let mut in_data = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                b"Sheet" => {
+                    let name = read_attributes(&element).into_iter().find(|(key, _)| key == "Name").map(|(_, name)| name).unwrap_or_default();
+                    sheets.push((name, vec![]));
+                },
+                b"Data" => in_data = true,
+                b"Param" if in_data => {
+                    if let Some((_, rows)) = sheets.last_mut() {
+                        rows.push(read_attributes(&element));
+                    }
+                },
+                _ => (),
+            },
+            Event::End(element) if element.name().as_ref() == b"Data" => in_data = false,
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(sheets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{csv_to_xml, yaml_to_xml};
+
+    const BASE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Book Count="1">
+	<Sheet Name="Item" Count="2">
+		<Header>
+			<Param Name="ID" Ident="Iid" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Price" Ident="Price" Type="int" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			<Param Iid="IID_Sword" Price="100" />
+			<Param Iid="IID_Axe" Price="150" />
+		</Data>
+	</Sheet>
+</Book>"#;
+
+    fn key(key: &str) -> RowKey {
+        RowKey { sheet: "Item".to_owned(), key: key.to_owned() }
+    }
+
+    #[test]
+    fn only_changed_rows_count() {
+        let base = BaseRows::new(BASE).unwrap();
+
+        // XML patches are full copies of the book
+        let xml = BASE.replace(r#"Iid="IID_Axe" Price="150""#, r#"Iid="IID_Axe" Price="200""#);
+        assert_eq!(base.changed_rows(&xml).unwrap(), [key("IID_Axe")]);
+
+        let csv = csv_to_xml("Iid,Price\nIID_Sword,100\nIID_Lance,300\n", BASE).unwrap();
+        assert_eq!(base.changed_rows(&csv).unwrap(), [key("IID_Lance")]);
+    }
+
+    #[test]
+    fn rows_changed_by_several_mods_conflict() {
+        let base = BaseRows::new(BASE).unwrap();
+
+        let xml = BASE.replace(r#"Price="150""#, r#"Price="200""#);
+        let yaml = yaml_to_xml("- Iid: IID_Axe\n  Price: 50\n- Iid: IID_Sword\n  Price: 1\n", BASE).unwrap();
+        let csv = csv_to_xml("Iid,Price\nIID_Sword,2\n", BASE).unwrap();
+
+        let patches = [
+            PatchRows { source: "sd:/mods/a/patches/xml/Item.xml", mod_root: "sd:/mods/a", converted: false, rows: base.changed_rows(&xml).unwrap() },
+            PatchRows { source: "sd:/mods/b/patches/xml/Item.yaml", mod_root: "sd:/mods/b", converted: true, rows: base.changed_rows(&yaml).unwrap() },
+            PatchRows { source: "sd:/mods/b/patches/xml/Item.csv", mod_root: "sd:/mods/b", converted: true, rows: base.changed_rows(&csv).unwrap() },
+        ];
+
+        // IID_Sword is only changed by a single mod
+        assert_eq!(
+            find_conflicts(&patches),
+            [RowConflict {
+                row: key("IID_Axe"),
+                sources: vec!["sd:/mods/a/patches/xml/Item.xml".to_owned(), "sd:/mods/b/patches/xml/Item.yaml".to_owned()],
+            }]
+        );
+    }
+
+    #[test]
+    fn xml_patches_alone_do_not_conflict() {
+        let patches = [
+            PatchRows { source: "a", mod_root: "sd:/mods/a", converted: false, rows: vec![key("IID_Axe")] },
+            PatchRows { source: "b", mod_root: "sd:/mods/b", converted: false, rows: vec![key("IID_Axe")] },
+        ];
+
+        assert!(find_conflicts(&patches).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+//! Conversion of spreadsheet-friendly patch formats (CSV, YAML) into the romfs XML layout, so they can be merged like any other XML patch.
+//!
+//! Column names are the attribute names used by the rows of the sheet (`Iid`, `Name`, ...), optionally prefixed by `@`.
+//! The base file is used to know which sheets and columns exist, so typos are reported instead of silently producing a broken sheet.
+
+use std::fmt;
+
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Reader, Writer,
+};
+
+/// Extensions that are accepted for patches in `patches/xml`, in the order they are applied for a single mod.
+pub const PATCH_EXTENSIONS: [&str; 3] = ["xml", "csv", "yaml"];
+
+#[derive(Debug)]
+pub enum ConvertError {
+    Csv(csv::Error),
+    Yaml(serde_yaml::Error),
+    Xml(quick_xml::Error),
+    MissingSheet,
+    UnknownSheet(String),
+    UnknownColumn { sheet: String, column: String },
+    InvalidRows(String),
+    InvalidValue { sheet: String, column: String },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Csv(err) => write!(f, "the CSV file could not be read: {}", err),
+            ConvertError::Yaml(err) => write!(f, "the YAML file could not be read: {}", err),
+            ConvertError::Xml(err) => write!(f, "the base XML could not be read: {}", err),
+            ConvertError::MissingSheet => write!(f, "the base XML does not have any sheet"),
+            ConvertError::UnknownSheet(sheet) => write!(f, "there is no sheet named `{}` in this book", sheet),
+            ConvertError::UnknownColumn { sheet, column } => {
+                write!(f, "the column `{}` does not exist in sheet `{}`, make sure it matches one of the attributes of the sheet", column, sheet)
+            },
+            ConvertError::InvalidRows(sheet) => write!(f, "the rows of sheet `{}` must be a list", sheet),
+            ConvertError::InvalidValue { sheet, column } => {
+                write!(f, "the value for column `{}` in sheet `{}` must be a single value or a list of values", column, sheet)
+            },
+        }
+    }
+}
+
+impl From<csv::Error> for ConvertError {
+    fn from(err: csv::Error) -> Self {
+        ConvertError::Csv(err)
+    }
+}
+
+impl From<serde_yaml::Error> for ConvertError {
+    fn from(err: serde_yaml::Error) -> Self {
+        ConvertError::Yaml(err)
+    }
+}
+
+impl From<quick_xml::Error> for ConvertError {
+    fn from(err: quick_xml::Error) -> Self {
+        ConvertError::Xml(err)
+    }
+}
+
+/// The layout of a sheet as found in the base XML.
+pub(crate) struct SheetLayout {
+    /// Attributes of the `<Sheet>` element, such as `Name` and `Count`.
+    attributes: Vec<(String, String)>,
+    /// Attributes of every `<Param>` found in the `<Header>`.
+    params: Vec<Vec<(String, String)>>,
+}
+
+impl SheetLayout {
+    pub(crate) fn name(&self) -> &str {
+        self.attributes.iter().find(|(key, _)| key == "Name").map(|(_, value)| value.as_str()).unwrap_or_default()
+    }
+
+    /// The attribute names the rows of this sheet are allowed to use.
+    pub(crate) fn columns(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().filter_map(|param| param.iter().find(|(key, _)| key == "Ident").map(|(_, value)| value.as_str()))
+    }
+}
+
+type Row = Vec<(String, String)>;
+
+/// Convert a CSV patch to XML. The file targets the first sheet of the book, and the first line holds the column names.
+pub fn csv_to_xml(csv: &str, base: &str) -> Result<String, ConvertError> {
+    let layouts = read_layouts(base)?;
+    let layout = layouts.first().ok_or(ConvertError::MissingSheet)?;
+
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv.as_bytes());
+
+    let headers: Vec<String> = reader.headers()?.iter().map(|header| header.trim().trim_start_matches('@').to_string()).collect();
+
+    let rows = reader
+        .records()
+        .map(|record| {
+            record.map(|record| {
+                headers
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(str::to_string))
+                    .collect::<Row>()
+            })
+        })
+        .collect::<Result<Vec<Row>, _>>()?;
+
+    write_book(&[(layout, rows)])
+}
+
+/// Convert a YAML patch to XML.
+///
+/// The file is either a list of rows for the first sheet of the book, or a map of sheet names to their list of rows.
+/// Lists of values are joined with `;`, which is what array attributes use.
+pub fn yaml_to_xml(yaml: &str, base: &str) -> Result<String, ConvertError> {
+    let layouts = read_layouts(base)?;
+    let first = layouts.first().ok_or(ConvertError::MissingSheet)?;
+
+    let sheets: Vec<(&SheetLayout, serde_yaml::Value)> = match serde_yaml::from_str(yaml)? {
+        serde_yaml::Value::Mapping(mapping) => mapping
+            .into_iter()
+            .map(|(name, rows)| {
+                let name = yaml_scalar(&name).unwrap_or_default();
+                let layout = layouts.iter().find(|layout| layout.name() == name).ok_or(ConvertError::UnknownSheet(name))?;
+                Ok((layout, rows))
+            })
+            .collect::<Result<_, ConvertError>>()?,
+        rows => vec![(first, rows)],
+    };
+
+    let sheets = sheets
+        .into_iter()
+        .map(|(layout, rows)| {
+            let rows = match rows {
+                serde_yaml::Value::Sequence(rows) => rows,
+                serde_yaml::Value::Null => vec![],
+                _ => return Err(ConvertError::InvalidRows(layout.name().to_string())),
+            };
+
+            let rows = rows
+                .iter()
+                .map(|row| {
+                    row.as_mapping()
+                        .into_iter()
+                        .flatten()
+                        .map(|(column, value)| {
+                            let column = yaml_scalar(column).unwrap_or_default().trim_start_matches('@').to_string();
+
+                            let value = match value {
+                                serde_yaml::Value::Sequence(values) => values.iter().map(yaml_scalar).collect::<Option<Vec<_>>>().map(|values| values.join(";")),
+                                value => yaml_scalar(value),
+                            }
+                            .ok_or_else(|| ConvertError::InvalidValue { sheet: layout.name().to_string(), column: column.clone() })?;
+
+                            Ok((column, value))
+                        })
+                        .collect::<Result<Row, ConvertError>>()
+                })
+                .collect::<Result<Vec<Row>, ConvertError>>()?;
+
+            Ok((layout, rows))
+        })
+        .collect::<Result<Vec<_>, ConvertError>>()?;
+
+    write_book(&sheets)
+}
+
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Null => Some(String::new()),
+        serde_yaml::Value::Bool(value) => Some(value.to_string()),
+        serde_yaml::Value::Number(value) => Some(value.to_string()),
+        serde_yaml::Value::String(value) => Some(value.to_owned()),
+        _ => None,
+    }
+}
+
+/// Read the name, attributes and header of every sheet in the base XML.
+pub(crate) fn read_layouts(base: &str) -> Result<Vec<SheetLayout>, ConvertError> {
+    let mut reader = Reader::from_str(base.trim_start_matches(|c| c != '<'));
+    reader.trim_text(true);
+
+    let mut layouts = vec![];
+    let mut in_header = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                b"Sheet" => layouts.push(SheetLayout {
+                    attributes: read_attributes(&element),
+                    params: vec![],
+                }),
+                b"Header" => in_header = true,
+                b"Param" if in_header => {
+                    if let Some(layout) = layouts.last_mut() {
+                        layout.params.push(read_attributes(&element));
+                    }
+                },
+                _ => (),
+            },
+            Event::End(element) if element.name().as_ref() == b"Header" => in_header = false,
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(layouts)
+}
+
+pub(crate) fn read_attributes(element: &BytesStart) -> Vec<(String, String)> {
+    element
+        .attributes()
+        .flatten()
+        .map(|attribute| {
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+            let value = attribute.unescape_value().map(|value| value.to_string()).unwrap_or_default();
+            (key, value)
+        })
+        .collect()
+}
+
+/// Write the rows of each sheet in the same layout as the romfs XML files, using the header of the base file.
+fn write_book(sheets: &[(&SheetLayout, Vec<Row>)]) -> Result<String, ConvertError> {
+    // Make sure every column exists before writing anything
+    for (layout, rows) in sheets {
+        if let Some((column, _)) = rows.iter().flatten().find(|(column, _)| !layout.columns().any(|known| known == column)) {
+            return Err(ConvertError::UnknownColumn { sheet: layout.name().to_string(), column: column.to_owned() });
+        }
+    }
+
+    // romfs xml files use \t for indentation
+    let mut writer = Writer::new_with_indent(Vec::new(), b'\t', 1);
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+
+    let count = sheets.len().to_string();
+    writer.write_event(Event::Start(BytesStart::new("Book").with_attributes([("Count", count.as_str())])))?;
+
+    for (layout, rows) in sheets {
+        let attributes = layout.attributes.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+        writer.write_event(Event::Start(BytesStart::new("Sheet").with_attributes(attributes)))?;
+
+        writer.write_event(Event::Start(BytesStart::new("Header")))?;
+        for param in &layout.params {
+            let attributes = param.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+            writer.write_event(Event::Empty(BytesStart::new("Param").with_attributes(attributes)))?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("Header")))?;
+
+        writer.write_event(Event::Start(BytesStart::new("Data")))?;
+        for row in rows {
+            // Follow the order of the header so the result looks like the original files
+            let attributes = layout
+                .columns()
+                .filter_map(|column| row.iter().find(|(key, _)| key == column).map(|(key, value)| (key.as_str(), value.as_str())));
+            writer.write_event(Event::Empty(BytesStart::new("Param").with_attributes(attributes)))?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("Data")))?;
+
+        writer.write_event(Event::End(BytesEnd::new("Sheet")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("Book")))?;
+
+    Ok(String::from_utf8(writer.into_inner()).expect("the XML writer should only produce UTF8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<Book Count="2">
+	<Sheet Name="Item" Count="1">
+		<Header>
+			<Param Name="ID" Ident="Iid" Type="string" Min="" Max="" Chg="" />
+			<Param Name="Price" Ident="Price" Type="int" Min="" Max="" Chg="" />
+			<Param Name="Tags" Ident="Tags" Type="string[]" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			<Param Iid="IID_Vanilla" Price="100" Tags="" />
+		</Data>
+	</Sheet>
+	<Sheet Name="ItemCategory" Count="1">
+		<Header>
+			<Param Name="Name" Ident="Name" Type="string" Min="" Max="" Chg="" />
+		</Header>
+		<Data>
+			<Param Name="Sword" />
+		</Data>
+	</Sheet>
+</Book>"#;
+
+    #[test]
+    fn csv_rows_follow_the_header_order() {
+        let xml = csv_to_xml("Price,@Iid\n250,\"IID_New, Improved\"\n", BASE).unwrap();
+
+        assert!(xml.contains(r#"<Sheet Name="Item" Count="1">"#));
+        assert!(xml.contains(r#"<Param Iid="IID_New, Improved" Price="250"/>"#));
+        assert!(!xml.contains("ItemCategory"));
+    }
+
+    #[test]
+    fn csv_unknown_columns_are_reported() {
+        let err = csv_to_xml("Iid,Prise\nIID_New,250\n", BASE).unwrap_err();
+
+        assert!(matches!(err, ConvertError::UnknownColumn { ref column, .. } if column == "Prise"));
+    }
+
+    #[test]
+    fn yaml_targets_sheets_by_name() {
+        let yaml = "ItemCategory:\n  - Name: Axe\nItem:\n  - Iid: IID_New\n    Price: 250\n    Tags: [Heavy, Rare]\n";
+        let xml = yaml_to_xml(yaml, BASE).unwrap();
+
+        assert!(xml.contains(r#"<Param Name="Axe"/>"#));
+        assert!(xml.contains(r#"<Param Iid="IID_New" Price="250" Tags="Heavy;Rare"/>"#));
+        assert!(xml.contains(r#"<Book Count="2">"#));
+    }
+
+    #[test]
+    fn yaml_list_targets_the_first_sheet() {
+        let xml = yaml_to_xml("- Iid: IID_New\n", BASE).unwrap();
+
+        assert!(xml.contains(r#"<Param Iid="IID_New"/>"#));
+    }
+
+    #[test]
+    fn yaml_unknown_sheets_are_reported() {
+        let err = yaml_to_xml("Weapon:\n  - Iid: IID_New\n", BASE).unwrap_err();
+
+        assert!(matches!(err, ConvertError::UnknownSheet(ref sheet) if sheet == "Weapon"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Optional export of the gamedata patching results to `sd:/engage_patches/`, to help modders figure out what their patches actually did.
+//!
+//! Nothing is written unless the user asked for it, since this costs SD writes on every boot.
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{LazyLock, Mutex, RwLock},
+};
+
+use differ::{Differ, Tag};
+use serde::Serialize;
+use strum_macros::{Display, EnumString, FromRepr};
+
+pub const DUMP_MODE_PATH: &str = "sd:/engage/config/patch_dump";
+pub const DUMP_BASE_PATH: &str = "sd:/engage/config/patch_dump_base";
+
+const DUMP_DIRECTORY: &str = "sd:/engage_patches";
+
+/// Lines of unchanged context to print around every change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+#[repr(i32)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, EnumString, Display, FromRepr)]
+pub enum DumpMode {
+    /// Don't write anything.
+    #[default]
+    Off,
+    /// Write the merged sheet once every patch has been applied.
+    Final,
+    /// Write the sheet after every patch is applied, then the merged sheet.
+    Steps,
+    /// Write a unified diff between the base sheet and the merged sheet.
+    Diff,
+}
+
+pub static DUMP_MODE: LazyLock<RwLock<DumpMode>> = LazyLock::new(|| {
+    let mode = std::fs::read_to_string(DUMP_MODE_PATH).ok().and_then(|mode| DumpMode::from_str(mode.trim()).ok()).unwrap_or_default();
+
+    RwLock::new(mode)
+});
+
+/// Export the unpatched version of the sheets. This is always done in debug builds.
+pub static DUMP_BASE: LazyLock<RwLock<bool>> = LazyLock::new(|| RwLock::new(std::path::Path::new(DUMP_BASE_PATH).exists()));
+
+/// What was dumped for every book so far, written to `index.yaml` next to the dumps.
+static DUMP_INDEX: LazyLock<Mutex<BTreeMap<String, DumpEntry>>> = LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(Serialize, Default)]
+struct DumpEntry {
+    mode: DumpMode,
+    files: Vec<String>,
+    /// Where the patches came from, in the order they were applied.
+    patches: Vec<String>,
+}
+
+pub fn get_mode() -> DumpMode {
+    *DUMP_MODE.read().unwrap()
+}
+
+/// Whether the unpatched sheets are exported, to avoid serializing them for nothing.
+pub fn wants_base() -> bool {
+    cfg!(debug_assertions) || *DUMP_BASE.read().unwrap()
+}
+
+/// Write the unpatched sheet as `base_<Sheet>.xml` if requested and it hasn't been written yet.
+pub fn dump_base(book_name: &str, base: &str) {
+    if !wants_base() {
+        return;
+    }
+
+    let filename = format!("base_{}.xml", book_name);
+    let path = format!("{}/{}", DUMP_DIRECTORY, filename);
+
+    // write if it doesn't exist
+    if !std::path::Path::new(&path).exists() && write(&path, base) {
+        record(book_name, &filename, None);
+    }
+}
+
+/// Write the state of a sheet after the patch at `idx` was applied, if the user wants every step.
+pub fn dump_step(book_name: &str, idx: usize, content: &str) {
+    if get_mode() != DumpMode::Steps {
+        return;
+    }
+
+    let filename = format!("{}#{}.xml", book_name, idx);
+
+    if write(&format!("{}/{}", DUMP_DIRECTORY, filename), content) {
+        record(book_name, &filename, None);
+    }
+}
+
+/// Write the merged sheet, or its differences with the base sheet, depending on the dump mode.
+///
+/// `sources` lists where every applied patch came from, and is recorded in the index.
+pub fn dump_final(book_name: &str, base: &str, merged: &str, sources: &[String]) {
+    let (filename, content) = match get_mode() {
+        DumpMode::Off => return,
+        DumpMode::Final | DumpMode::Steps => (format!("{}.xml", book_name), merged.to_string()),
+        DumpMode::Diff => (
+            format!("{}.diff", book_name),
+            unified_diff(base, merged, &format!("base_{}.xml", book_name), &format!("{}.xml", book_name)),
+        ),
+    };
+
+    if write(&format!("{}/{}", DUMP_DIRECTORY, filename), &content) {
+        record(book_name, &filename, Some(sources));
+    }
+}
+
+fn write(path: &str, content: &str) -> bool {
+    let _ = std::fs::create_dir_all(DUMP_DIRECTORY);
+
+    match std::fs::write(path, content) {
+        Ok(_) => true,
+        Err(err) => {
+            println!("Could not dump '{}': {}", path, err);
+            false
+        },
+    }
+}
+
+fn record(book_name: &str, filename: &str, sources: Option<&[String]>) {
+    let mut index = DUMP_INDEX.lock().unwrap();
+
+    let entry = index.entry(book_name.to_string()).or_default();
+    entry.mode = get_mode();
+
+    if !entry.files.iter().any(|file| file == filename) {
+        entry.files.push(filename.to_string());
+    }
+
+    if let Some(sources) = sources {
+        entry.patches = sources.to_vec();
+    }
+
+    match serde_yaml::to_string(&*index) {
+        Ok(out) => {
+            write(&format!("{}/index.yaml", DUMP_DIRECTORY), &out);
+        },
+        Err(err) => println!("Could not serialize the patch dump index: {}", err),
+    }
+}
+
+/// Produce a unified diff between two versions of a file, line by line.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let spans = Differ::new(&old_lines, &new_lines).spans();
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    // Group the changes that are close enough to share their context
+    let changes: Vec<_> = spans.iter().filter(|span| matches!(span.tag, Tag::Insert | Tag::Delete | Tag::Replace)).collect();
+
+    let mut idx = 0;
+
+    while idx < changes.len() {
+        let mut last = idx;
+
+        while last + 1 < changes.len() && changes[last + 1].a_start - changes[last].a_end <= DIFF_CONTEXT * 2 {
+            last += 1;
+        }
+
+        let a_start = changes[idx].a_start.saturating_sub(DIFF_CONTEXT);
+        let a_end = (changes[last].a_end + DIFF_CONTEXT).min(old_lines.len());
+        let b_start = changes[idx].b_start.saturating_sub(DIFF_CONTEXT);
+        let b_end = (changes[last].b_end + DIFF_CONTEXT).min(new_lines.len());
+
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", a_start + 1, a_end - a_start, b_start + 1, b_end - b_start));
+
+        let mut a = a_start;
+
+        for change in &changes[idx..=last] {
+            // Context leading to the change
+            old_lines[a..change.a_start].iter().for_each(|line| out.push_str(&format!(" {}\n", line)));
+
+            old_lines[change.a_start..change.a_end].iter().for_each(|line| out.push_str(&format!("-{}\n", line)));
+            new_lines[change.b_start..change.b_end].iter().for_each(|line| out.push_str(&format!("+{}\n", line)));
+
+            a = change.a_end;
+        }
+
+        // Context following the last change
+        old_lines[a..a_end].iter().for_each(|line| out.push_str(&format!(" {}\n", line)));
+
+        idx = last + 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified_diff;
+
+    #[test]
+    fn insertion_at_the_top() {
+        let diff = unified_diff("a\nb\nc\nd\ne", "x\na\nb\nc\nd\ne", "old", "new");
+
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,3 +1,4 @@\n+x\n a\n b\n c\n");
+    }
+
+    #[test]
+    fn deletion_at_the_end() {
+        let diff = unified_diff("a\nb\nc\nd\ne", "a\nb\nc\nd", "old", "new");
+
+        assert_eq!(diff, "--- old\n+++ new\n@@ -2,4 +2,3 @@\n b\n c\n d\n-e\n");
+    }
+
+    #[test]
+    fn nearby_changes_share_a_hunk() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10";
+        let new = "1\n2\nthree\n4\n5\nsix\n7\n8\n9\n10";
+
+        let diff = unified_diff(old, new, "old", "new");
+
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,9 +1,9 @@\n 1\n 2\n-3\n+three\n 4\n 5\n-6\n+six\n 7\n 8\n 9\n");
+    }
+
+    #[test]
+    fn identical_files_only_have_a_header() {
+        let text = "<Book>\n\t<Sheet />\n</Book>";
+
+        assert_eq!(unified_diff(text, text, "old", "new"), "--- old\n+++ new\n");
+    }
+}
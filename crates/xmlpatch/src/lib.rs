@@ -0,0 +1,14 @@
+//! Merging of patches into the XML books of the game, without needing the game to be running.
+//!
+//! Also converts the CSV and YAML patch formats to XML, finds the rows several mods change, and exports what the patches did for modders to look at.
+
+pub mod conflict;
+pub mod convert;
+pub mod dump;
+mod patch;
+
+pub use patch::*;
+
+pub trait XmlPatch {
+    fn patch(&mut self, patch: Self, original: &Self);
+}
@@ -0,0 +1,218 @@
+use std::hash::Hash;
+
+use astra_formats::{indexmap::IndexMap, Sheet};
+use differ::{Differ, Tag};
+
+use super::XmlPatch;
+
+/// Implemented by rows that can be identified by a unique key in their sheet, so a patch can replace them without knowing their position.
+pub trait Keyed {
+    type Key: Eq + Hash + Clone;
+
+    fn key(&self) -> Self::Key;
+}
+
+/// Implement [`XmlPatch`] for a book by patching each of the listed sheets.
+///
+/// Every field needs to be a `Sheet` that implements [`XmlPatch`], which is the case for `Sheet<Vec<T>>` with keyed rows and `Sheet<IndexMap<K, Vec<T>>>` groups.
+///
+/// ```ignore
+/// impl_xml_patch!(VibrationEventBook { vibration_events, vibration_event_chains });
+/// ```
+#[macro_export]
+macro_rules! impl_xml_patch {
+    ($book:ty { $($sheet:ident),* $(,)? }) => {
+        impl $crate::XmlPatch for $book {
+            fn patch(&mut self, patch: Self, original: &Self) {
+                $(
+                    $crate::XmlPatch::patch(&mut self.$sheet, patch.$sheet, &original.$sheet);
+                )*
+            }
+        }
+    };
+}
+
+/// Sheets where every row has its own key (`@Name`, `@Iid`, ...).
+///
+/// Rows that are identical to the original are ignored so a patch only overrides what it actually changed.
+/// Rows that differ replace the current row with the same key, and new keys are appended at the end.
+impl<T> XmlPatch for Sheet<Vec<T>>
+where
+    T: Keyed + PartialEq + Clone,
+{
+    fn patch(&mut self, patch: Self, original: &Self) {
+        patch.data.into_iter().for_each(|row| {
+            let key = row.key();
+
+            // Unchanged from the original file, don't let it undo the work of a previous patch
+            if original.data.iter().any(|original_row| original_row.key() == key && *original_row == row) {
+                return;
+            }
+
+            match self.data.iter_mut().find(|current_row| current_row.key() == key) {
+                Some(current_row) => *current_row = row,
+                None => self.data.push(row),
+            }
+        });
+    }
+}
+
+/// Sheets where rows are grouped under a key, like the `@Condition` of a Support set or the `@Name` of a shop.
+///
+/// Each group is diffed against its original version and only the spans that were inserted, replaced or deleted are applied.
+impl<K, T> XmlPatch for Sheet<IndexMap<K, Vec<T>>>
+where
+    K: Eq + Hash + Clone,
+    T: Eq + Hash + Clone,
+{
+    fn patch(&mut self, patch: Self, original: &Self) {
+        patch.data.into_iter().for_each(|(key, rows)| {
+            // Can eventually finish with a empty group
+            if rows.is_empty() {
+                return;
+            }
+
+            // Grab the original group for comparisons
+            let original_group = original.data.get(&key);
+
+            // Get or add the current group to the patched book
+            let current_group = self.data.entry(key).or_insert_with(Vec::new);
+
+            patch_group(current_group, original_group, &rows);
+        });
+    }
+}
+
+/// Apply the differences between a patched group and its original version to the current group.
+///
+/// If there is no original group, the patch is compared to the current group instead.
+pub fn patch_group<T>(current_group: &mut Vec<T>, original_group: Option<&Vec<T>>, rows: &[T])
+where
+    T: Eq + Hash + Clone,
+{
+    // Compare to the original group if it exists, or the patched one if it does not
+    let spans = Differ::new(original_group.unwrap_or(current_group), rows).spans();
+
+    // Process in reverse order so insertions/deletions don't shift the indices
+    for span in spans.iter().rev() {
+        match span.tag {
+            Tag::Insert => {
+                for i in (span.b_start..span.b_end).rev() {
+                    current_group.insert(span.a_start, rows[i].clone())
+                }
+            },
+            Tag::Replace => {
+                current_group.splice(span.a_start..span.a_end, rows[span.b_start..span.b_end].iter().cloned());
+            },
+            Tag::Delete => {
+                for i in (span.a_start..span.a_end).rev() {
+                    current_group.remove(i);
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use astra_formats::{indexmap::IndexMap, Sheet, SheetHeader};
+
+    use super::Keyed;
+    use crate::XmlPatch;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Row {
+        name: String,
+        value: u32,
+    }
+
+    impl Keyed for Row {
+        type Key = String;
+
+        fn key(&self) -> Self::Key {
+            self.name.clone()
+        }
+    }
+
+    #[derive(Clone)]
+    struct SyntheticBook {
+        rows: Sheet<Vec<Row>>,
+        groups: Sheet<IndexMap<String, Vec<Row>>>,
+    }
+
+    crate::impl_xml_patch!(SyntheticBook { rows, groups });
+
+    fn row(name: &str, value: u32) -> Row {
+        Row { name: name.to_string(), value }
+    }
+
+    fn sheet<T>(data: T) -> Sheet<T> {
+        Sheet {
+            name: String::from("Synthetic"),
+            header: SheetHeader { params: vec![] },
+            data,
+        }
+    }
+
+    fn book(rows: Vec<Row>, groups: Vec<(&str, Vec<Row>)>) -> SyntheticBook {
+        SyntheticBook {
+            rows: sheet(rows),
+            groups: sheet(groups.into_iter().map(|(key, rows)| (key.to_string(), rows)).collect()),
+        }
+    }
+
+    #[test]
+    fn keyed_rows_are_replaced_and_appended() {
+        let original = book(vec![row("A", 1), row("B", 2)], vec![]);
+        let mut current = original.clone();
+
+        current.patch(book(vec![row("B", 20), row("C", 3)], vec![]), &original);
+
+        assert_eq!(current.rows.data, vec![row("A", 1), row("B", 20), row("C", 3)]);
+    }
+
+    #[test]
+    fn unchanged_rows_do_not_undo_previous_patches() {
+        let original = book(vec![row("A", 1), row("B", 2)], vec![]);
+        let mut current = original.clone();
+
+        current.patch(book(vec![row("A", 10)], vec![]), &original);
+        // This patch ships the whole sheet but only touches B
+        current.patch(book(vec![row("A", 1), row("B", 20)], vec![]), &original);
+
+        assert_eq!(current.rows.data, vec![row("A", 10), row("B", 20)]);
+    }
+
+    #[test]
+    fn groups_apply_insertions_replacements_and_deletions() {
+        let original = book(vec![], vec![("G", vec![row("A", 1), row("B", 2), row("C", 3)])]);
+        let mut current = original.clone();
+
+        current.patch(book(vec![], vec![("G", vec![row("A", 1), row("B", 20), row("D", 4)])]), &original);
+
+        assert_eq!(current.groups.data["G"], vec![row("A", 1), row("B", 20), row("D", 4)]);
+    }
+
+    #[test]
+    fn groups_from_different_patches_are_merged() {
+        let original = book(vec![], vec![("G", vec![row("A", 1), row("B", 2)])]);
+        let mut current = original.clone();
+
+        current.patch(book(vec![], vec![("G", vec![row("A", 1), row("B", 2), row("C", 3)])]), &original);
+        current.patch(book(vec![], vec![("G", vec![row("A", 10), row("B", 2)])]), &original);
+
+        assert_eq!(current.groups.data["G"], vec![row("A", 10), row("B", 2), row("C", 3)]);
+    }
+
+    #[test]
+    fn new_groups_are_added() {
+        let original = book(vec![], vec![("G", vec![row("A", 1)])]);
+        let mut current = original.clone();
+
+        current.patch(book(vec![], vec![("H", vec![row("B", 2)])]), &original);
+
+        assert_eq!(current.groups.data["G"], vec![row("A", 1)]);
+        assert_eq!(current.groups.data["H"], vec![row("B", 2)]);
+    }
+}
@@ -158,6 +158,9 @@ pub fn il2cpp_init_hook(domain_name: *const i8) -> i32 {
         cobalt::support::reliancedata_trygetexp,
     );
 
+    // Start merging the patched sheets ahead of time so the database doesn't have to wait on them
+    gamedata::premerge::premerge_patched_sheets();
+
     // load up vibration data
     initialize_vibration_data();
 